@@ -0,0 +1,13 @@
+CREATE TABLE bookings(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   room_id uuid NOT NULL
+      REFERENCES rooms (id),
+   guest_name TEXT NOT NULL,
+   guest_email TEXT NOT NULL,
+   check_in DATE NOT NULL,
+   check_out DATE NOT NULL,
+   status TEXT NOT NULL,
+   created_at timestamptz NOT NULL,
+   CHECK(check_out > check_in)
+);
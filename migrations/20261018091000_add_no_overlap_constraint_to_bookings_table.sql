@@ -0,0 +1,11 @@
+-- Required to mix the equality on uuid with the range overlap in one GiST index
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+-- A room can't be booked twice for the same night.
+-- Stays are half-open ranges, so checking out and checking in on the same day is allowed.
+ALTER TABLE bookings
+ADD CONSTRAINT bookings_no_overlapping_stays
+EXCLUDE USING gist (
+   room_id WITH =,
+   daterange(check_in, check_out, '[)') WITH &&
+) WHERE (status <> 'cancelled');
@@ -0,0 +1,11 @@
+-- Deleting a host removes its rooms, and deleting a room removes its past stays.
+-- Routes refuse the delete while a room still has upcoming bookings.
+ALTER TABLE rooms
+DROP CONSTRAINT rooms_host_id_fkey,
+ADD CONSTRAINT rooms_host_id_fkey
+   FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE;
+
+ALTER TABLE bookings
+DROP CONSTRAINT bookings_room_id_fkey,
+ADD CONSTRAINT bookings_room_id_fkey
+   FOREIGN KEY (room_id) REFERENCES rooms (id) ON DELETE CASCADE;
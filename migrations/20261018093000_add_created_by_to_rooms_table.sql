@@ -0,0 +1,4 @@
+-- Rooms created before authentication was required have no author
+ALTER TABLE rooms
+ADD created_by uuid
+   REFERENCES users (user_id) ON DELETE SET NULL;
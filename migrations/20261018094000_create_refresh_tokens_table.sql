@@ -0,0 +1,14 @@
+-- Refresh tokens are opaque, only their SHA-256 hash is stored.
+-- Every rotation keeps the family of the token it replaces, so a reused
+-- token can revoke the whole session at once.
+CREATE TABLE refresh_tokens(
+   id uuid PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   family_id uuid NOT NULL,
+   token_hash TEXT NOT NULL UNIQUE,
+   expires_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL,
+   revoked_at timestamptz
+);
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
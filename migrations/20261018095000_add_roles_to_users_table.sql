@@ -0,0 +1,15 @@
+-- Every account is a guest unless promoted, host managers are scoped to
+-- the hosts listed in user_hosts
+ALTER TABLE users
+ADD role TEXT NOT NULL DEFAULT 'guest'
+   CHECK (role IN ('admin', 'host_manager', 'guest'));
+
+UPDATE users SET role = 'admin' WHERE user_id = 'ddf8994f-d522-4659-8d02-c1d479057be6';
+
+CREATE TABLE user_hosts(
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   host_id uuid NOT NULL
+      REFERENCES hosts (id) ON DELETE CASCADE,
+   PRIMARY KEY (user_id, host_id)
+);
@@ -0,0 +1,4 @@
+-- Scopes requested at login, rotated tokens are limited to them.
+-- NULL grants every scope of the role of the user.
+ALTER TABLE refresh_tokens
+ADD scope TEXT;
@@ -0,0 +1,17 @@
+-- A user may log in with any of the did:key identifiers linked to the account
+CREATE TABLE user_dids(
+   did TEXT PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- Nonces handed out to DID holders, each one can be answered only once
+CREATE TABLE did_challenges(
+   id uuid PRIMARY KEY,
+   did TEXT NOT NULL,
+   nonce TEXT NOT NULL UNIQUE,
+   expires_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL,
+   used_at timestamptz
+);
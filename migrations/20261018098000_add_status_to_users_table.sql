@@ -0,0 +1,14 @@
+-- Self-registered users have to confirm their email before they can log in,
+-- existing users are already active
+ALTER TABLE users
+ADD status TEXT NOT NULL DEFAULT 'active'
+   CHECK (status IN ('pending_confirmation', 'active'));
+
+-- Only the SHA-256 hash of the token sent in the confirmation email is stored
+CREATE TABLE registration_tokens(
+   token_hash TEXT PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   expires_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Only the SHA-256 hash of the token sent in the reset email is stored,
+-- a token is deleted once used
+CREATE TABLE password_reset_tokens(
+   token_hash TEXT PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   expires_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL
+);
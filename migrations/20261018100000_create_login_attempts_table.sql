@@ -0,0 +1,8 @@
+-- Failed logins counted per account and per client IP, `key` is either
+-- `account:<username>` or `ip:<address>`
+CREATE TABLE login_attempts(
+   key TEXT PRIMARY KEY,
+   failed_attempts INT NOT NULL,
+   last_failed_at timestamptz NOT NULL,
+   locked_until timestamptz
+);
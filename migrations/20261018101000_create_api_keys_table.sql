@@ -0,0 +1,17 @@
+-- Keys for machine-to-machine integrations, managed by admins.
+-- A key is `rbk_<prefix>_<secret>`, the prefix identifies it and only the
+-- SHA-256 hash of the secret is stored. It acts as its owner, limited to
+-- its scopes.
+CREATE TABLE api_keys(
+   id uuid PRIMARY KEY,
+   owner_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   name TEXT NOT NULL,
+   prefix TEXT NOT NULL UNIQUE,
+   secret_hash TEXT NOT NULL,
+   scope TEXT NOT NULL,
+   expires_at timestamptz,
+   last_used_at timestamptz,
+   created_at timestamptz NOT NULL,
+   revoked_at timestamptz
+);
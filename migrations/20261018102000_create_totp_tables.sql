@@ -0,0 +1,31 @@
+-- TOTP second factor of a user, it is enabled once `confirmed_at` is set.
+-- `last_used_step` keeps a code from being replayed within its time window.
+CREATE TABLE user_totp(
+   user_id uuid PRIMARY KEY
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   secret TEXT NOT NULL,
+   confirmed_at timestamptz,
+   last_used_step BIGINT,
+   created_at timestamptz NOT NULL
+);
+
+-- Single-use recovery codes, only their SHA-256 hash is stored
+CREATE TABLE totp_recovery_codes(
+   code_hash TEXT PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   used_at timestamptz,
+   created_at timestamptz NOT NULL
+);
+
+-- Handed out by `/login` to a user with TOTP enabled, exchanged with a code
+-- for the real tokens
+CREATE TABLE mfa_challenges(
+   token_hash TEXT PRIMARY KEY,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   scope TEXT,
+   failed_attempts INTEGER NOT NULL DEFAULT 0,
+   expires_at timestamptz NOT NULL,
+   created_at timestamptz NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Statuses of the booking lifecycle, see `domain::BookingStatus`.
+-- pending -> confirmed -> checked_in -> checked_out, a pending or confirmed
+-- booking may be cancelled and a confirmed one marked as a no-show.
+ALTER TABLE bookings
+ADD CONSTRAINT bookings_status_check
+CHECK (status IN ('pending', 'confirmed', 'checked_in', 'checked_out', 'cancelled', 'no_show'));
@@ -0,0 +1,32 @@
+-- Prices are in the smallest unit of the currency, e.g. cents.
+-- A refundable policy is free until `free_cancellation_days` before check-in
+-- and charges `cancellation_penalty_percent` of the total afterwards, a
+-- non-refundable one charges the total.
+ALTER TABLE rooms
+ADD price_per_night BIGINT NOT NULL DEFAULT 0
+   CHECK (price_per_night >= 0),
+ADD cancellation_policy TEXT NOT NULL DEFAULT 'refundable'
+   CHECK (cancellation_policy IN ('refundable', 'non_refundable')),
+ADD free_cancellation_days INTEGER NOT NULL DEFAULT 0
+   CHECK (free_cancellation_days >= 0),
+ADD cancellation_penalty_percent SMALLINT NOT NULL DEFAULT 0
+   CHECK (cancellation_penalty_percent BETWEEN 0 AND 100);
+
+-- A booking keeps the price and the policy of the room at the time it was made
+ALTER TABLE bookings
+ADD total_price BIGINT NOT NULL DEFAULT 0
+   CHECK (total_price >= 0),
+ADD cancellation_policy TEXT NOT NULL DEFAULT 'refundable'
+   CHECK (cancellation_policy IN ('refundable', 'non_refundable')),
+ADD free_cancellation_days INTEGER NOT NULL DEFAULT 0
+   CHECK (free_cancellation_days >= 0),
+ADD cancellation_penalty_percent SMALLINT NOT NULL DEFAULT 0
+   CHECK (cancellation_penalty_percent BETWEEN 0 AND 100);
+
+CREATE TABLE booking_cancellations(
+   booking_id uuid PRIMARY KEY
+      REFERENCES bookings (id) ON DELETE CASCADE,
+   penalty BIGINT NOT NULL CHECK (penalty >= 0),
+   refund BIGINT NOT NULL CHECK (refund >= 0),
+   cancelled_at timestamptz NOT NULL
+);
@@ -0,0 +1,18 @@
+-- Each change of dates or room made to a booking, along with what it replaced
+CREATE TABLE booking_modifications(
+   id uuid NOT NULL,
+   PRIMARY KEY (id),
+   booking_id uuid NOT NULL
+      REFERENCES bookings (id) ON DELETE CASCADE,
+   previous_room_id uuid NOT NULL,
+   previous_check_in DATE NOT NULL,
+   previous_check_out DATE NOT NULL,
+   previous_total_price BIGINT NOT NULL,
+   room_id uuid NOT NULL,
+   check_in DATE NOT NULL,
+   check_out DATE NOT NULL,
+   total_price BIGINT NOT NULL,
+   modified_at timestamptz NOT NULL
+);
+
+CREATE INDEX booking_modifications_booking_id_idx ON booking_modifications (booking_id, modified_at);
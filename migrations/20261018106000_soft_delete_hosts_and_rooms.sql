@@ -0,0 +1,15 @@
+-- Deleted hosts and rooms are only marked as such, so that the bookings made
+-- for them, and their cancellation and modification history, are kept.
+-- Routes refuse the delete while a room still has upcoming bookings.
+ALTER TABLE hosts ADD COLUMN deleted_at timestamptz;
+ALTER TABLE rooms ADD COLUMN deleted_at timestamptz;
+
+ALTER TABLE rooms
+DROP CONSTRAINT rooms_host_id_fkey,
+ADD CONSTRAINT rooms_host_id_fkey
+   FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE RESTRICT;
+
+ALTER TABLE bookings
+DROP CONSTRAINT bookings_room_id_fkey,
+ADD CONSTRAINT bookings_room_id_fkey
+   FOREIGN KEY (room_id) REFERENCES rooms (id) ON DELETE RESTRICT;
@@ -3,7 +3,30 @@
 set -x
 set -eo pipefail
 
+# Optional prefix for the file names, e.g. `./scripts/init_jwt_keypair.sh next-`
+# generates a second pair to rotate to, see `jwt.keys` in `configuration/base.yaml`
+PREFIX="${1:-}"
+# RS256 or EdDSA, it must match the `alg` of the key in `jwt.keys`
+ALG="${ALG:=RS256}"
+
+if [ "${ALG}" = "EdDSA" ]; then
+  openssl genpkey -algorithm ed25519 -outform der > "${PREFIX}private-key.pk8"
+
+  # Public key used to verify the tokens, the raw 32 bytes expected by ring
+  # are the tail of the SubjectPublicKeyInfo
+  openssl pkey -inform DER -in "${PREFIX}private-key.pk8" \
+      -pubout \
+      -outform DER | tail -c 32 > "${PREFIX}public-key.der"
+  exit 0
+fi
+
 openssl genpkey -algorithm RSA \
     -pkeyopt rsa_keygen_bits:2048 \
     -pkeyopt rsa_keygen_pubexp:65537 | \
-  openssl pkcs8 -topk8 -nocrypt -outform der > private-key.pk8
+  openssl pkcs8 -topk8 -nocrypt -outform der > "${PREFIX}private-key.pk8"
+
+# Public key used to verify the tokens, in the PKCS#1 DER format expected by ring
+openssl rsa -inform DER -in "${PREFIX}private-key.pk8" \
+    -RSAPublicKey_out \
+    -outform DER \
+    -out "${PREFIX}public-key.der"
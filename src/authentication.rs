@@ -1,12 +1,38 @@
+mod algorithm;
+mod api_key;
 mod decode;
+mod did;
 mod domain;
 mod error;
+mod jwk;
+mod keys;
+mod login_throttle;
+mod middleware;
+mod opaque_token;
 mod password;
+mod password_reset;
+mod refresh_token;
+mod role;
+mod scope;
 mod sign;
+mod totp;
 mod verify;
 
+pub use algorithm::*;
+pub use api_key::*;
 pub use decode::*;
+pub use did::*;
 pub use domain::*;
+pub use jwk::*;
+pub use keys::*;
+pub use login_throttle::*;
+pub use middleware::*;
+pub use opaque_token::*;
 pub use password::*;
+pub use password_reset::*;
+pub use refresh_token::*;
+pub use role::*;
+pub use scope::*;
 pub use sign::*;
+pub use totp::*;
 pub use verify::*;
@@ -0,0 +1,151 @@
+use ring::{
+    rand::SystemRandom,
+    rsa::KeyPair,
+    signature::{
+        Ed25519KeyPair, UnparsedPublicKey, ED25519, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::error::MyError;
+
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+// Algorithms we sign access tokens with, see [RFC 7518 3.1] and [RFC 8037 3.1]
+//
+// [RFC 7518 3.1]: https://www.rfc-editor.org/rfc/rfc7518#section-3.1
+// [RFC 8037 3.1]: https://www.rfc-editor.org/rfc/rfc8037#section-3.1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Algorithm {
+    #[default]
+    #[serde(rename = "RS256")]
+    Rs256,
+    #[serde(rename = "EdDSA")]
+    EdDsa,
+}
+
+impl Algorithm {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "RS256" => Ok(Algorithm::Rs256),
+            "EdDSA" => Ok(Algorithm::EdDsa),
+            _ => Err(format!("{} is not a supported algorithm!", s)),
+        }
+    }
+
+    /// Sign the message with a private key in PKCS#8 DER
+    pub fn sign(&self, private_key: &[u8], message: &[u8]) -> Result<Vec<u8>, MyError> {
+        match self {
+            Algorithm::Rs256 => {
+                let key_pair =
+                    KeyPair::from_pkcs8(private_key).map_err(|_| MyError::BadPrivateKey)?;
+                let rng = SystemRandom::new();
+                let mut signature = vec![0; key_pair.public().modulus_len()];
+                key_pair
+                    .sign(&RSA_PKCS1_SHA256, &rng, message, &mut signature)
+                    .map_err(|_| MyError::Oom)?;
+
+                Ok(signature)
+            }
+            Algorithm::EdDsa => {
+                // openssl writes PKCS#8 v1 which has no public key to check against
+                let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(private_key)
+                    .map_err(|_| MyError::BadPrivateKey)?;
+
+                Ok(key_pair.sign(message).as_ref().to_vec())
+            }
+        }
+    }
+
+    /// Verify the signature with a public key, a PKCS#1 `RSAPublicKey` in DER for RS256
+    /// and the raw 32 bytes for EdDSA
+    pub fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let public_key = match self {
+            Algorithm::Rs256 => UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, public_key),
+            Algorithm::EdDsa => UnparsedPublicKey::new(&ED25519, public_key),
+        };
+
+        public_key.verify(message, signature).is_ok()
+    }
+
+    pub fn check_public_key(&self, public_key: &[u8]) -> Result<(), MyError> {
+        match self {
+            // The JWK encoding parses the DER
+            Algorithm::Rs256 => Ok(()),
+            Algorithm::EdDsa if public_key.len() == ED25519_PUBLIC_KEY_LEN => Ok(()),
+            Algorithm::EdDsa => Err(MyError::BadPublicKey),
+        }
+    }
+}
+
+impl AsRef<str> for Algorithm {
+    fn as_ref(&self) -> &str {
+        match self {
+            Algorithm::Rs256 => "RS256",
+            Algorithm::EdDsa => "EdDSA",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ring::signature::KeyPair as _;
+
+    use super::*;
+    use crate::authentication::{get_private_key_pk8, get_public_key_der};
+
+    #[test]
+    fn should_parse_what_it_names() {
+        for alg in [Algorithm::Rs256, Algorithm::EdDsa] {
+            assert_eq!(Algorithm::parse(alg.as_ref()), Ok(alg));
+        }
+        assert!(Algorithm::parse("none").is_err());
+        assert!(Algorithm::parse("HS256").is_err());
+    }
+
+    #[test]
+    fn should_verify_what_it_signs() {
+        let rsa_private_key = get_private_key_pk8("./private-key.pk8").unwrap();
+        let rsa_public_key = get_public_key_der("./public-key.der").unwrap();
+        let ed25519_private_key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let ed25519_public_key = Ed25519KeyPair::from_pkcs8(ed25519_private_key.as_ref())
+            .unwrap()
+            .public_key()
+            .as_ref()
+            .to_vec();
+        let test_cases = vec![
+            (Algorithm::Rs256, rsa_private_key, rsa_public_key),
+            (
+                Algorithm::EdDsa,
+                ed25519_private_key.as_ref().to_vec(),
+                ed25519_public_key,
+            ),
+        ];
+
+        for (alg, private_key, public_key) in test_cases {
+            let signature = alg.sign(&private_key, b"header.payload").unwrap();
+
+            assert!(
+                alg.verify(&public_key, b"header.payload", &signature),
+                "{:?}",
+                alg
+            );
+            assert!(
+                !alg.verify(&public_key, b"header.tampered", &signature),
+                "{:?}",
+                alg
+            );
+        }
+    }
+
+    #[test]
+    fn should_err_with_a_private_key_of_another_algorithm() {
+        let rsa_private_key = get_private_key_pk8("./private-key.pk8").unwrap();
+        let ed25519_private_key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+
+        assert!(Algorithm::EdDsa.sign(&rsa_private_key, b"message").is_err());
+        assert!(Algorithm::Rs256
+            .sign(ed25519_private_key.as_ref(), b"message")
+            .is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{generate_opaque_token, get_user_access, hash_opaque_token, AuthenticatedUser, Scope};
+
+const API_KEY_TAG: &str = "rbk";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ApiKeyError {
+    #[error("Invalid API key.")]
+    InvalidKey(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// An API key as listed to admins, its secret is never shown again after creation
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scope: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+pub struct NewApiKey {
+    pub owner_id: Uuid,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Split a `rbk_<prefix>_<secret>` key into its prefix and its secret
+///
+/// # Examples
+///
+/// ```
+/// use rush_booking::authentication::parse_api_key;
+///
+/// assert_eq!(parse_api_key("rbk_0a1b2c3d_s3cr_et"), Some(("0a1b2c3d", "s3cr_et")));
+/// assert_eq!(parse_api_key("0a1b2c3d_s3cr_et"), None);
+/// ```
+pub fn parse_api_key(key: &str) -> Option<(&str, &str)> {
+    let mut parts = key.splitn(3, '_');
+    if parts.next()? != API_KEY_TAG {
+        return None;
+    }
+    let prefix = parts.next().filter(|prefix| !prefix.is_empty())?;
+    let secret = parts.next().filter(|secret| !secret.is_empty())?;
+
+    Some((prefix, secret))
+}
+
+/// Store a new API key and return its id along with the plaintext key
+#[tracing::instrument(name = "Create API key", skip(new_api_key, pool))]
+pub async fn create_api_key(
+    new_api_key: &NewApiKey,
+    pool: &PgPool,
+) -> Result<(ApiKey, Secret<String>), anyhow::Error> {
+    let prefix = format!("{:08x}", rand::random::<u32>());
+    let secret = generate_opaque_token();
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (
+            id, owner_id, name, prefix, secret_hash, scope, expires_at, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, owner_id, name, prefix, scope, expires_at, last_used_at, created_at,
+            revoked_at
+        "#,
+        Uuid::new_v4(),
+        new_api_key.owner_id,
+        new_api_key.name,
+        prefix,
+        hash_opaque_token(&secret),
+        Scope::join(&new_api_key.scopes),
+        new_api_key.expires_at,
+        Utc::now(),
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to store the API key.")?;
+    let key = format!("{}_{}_{}", API_KEY_TAG, prefix, secret);
+
+    Ok((api_key, Secret::new(key)))
+}
+
+#[tracing::instrument(name = "List API keys", skip(pool))]
+pub async fn get_api_keys(pool: &PgPool) -> Result<Vec<ApiKey>, anyhow::Error> {
+    let api_keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, owner_id, name, prefix, scope, expires_at, last_used_at, created_at,
+            revoked_at
+        FROM api_keys
+        ORDER BY created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to retrieve the API keys.")?;
+
+    Ok(api_keys)
+}
+
+/// Revoke the API key, returns `false` when there is no such key
+#[tracing::instrument(name = "Revoke API key", skip(pool))]
+pub async fn revoke_api_key(id: Uuid, pool: &PgPool) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = COALESCE(revoked_at, now())
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to revoke the API key.")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Authenticate the owner of the API key, limited to the scopes of the key
+#[tracing::instrument(name = "Authenticate API key", skip(key, pool))]
+pub async fn authenticate_api_key(
+    key: &Secret<String>,
+    pool: &PgPool,
+) -> Result<AuthenticatedUser, ApiKeyError> {
+    let (prefix, secret) = parse_api_key(key.expose_secret())
+        .ok_or_else(|| ApiKeyError::InvalidKey(anyhow::anyhow!("Malformed API key.")))?;
+    let stored_key = sqlx::query!(
+        r#"
+        SELECT id, owner_id, secret_hash, scope
+        FROM api_keys
+        WHERE prefix = $1
+            AND revoked_at IS NULL
+            AND (expires_at IS NULL OR expires_at > now())
+        "#,
+        prefix,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the API key.")?
+    .filter(|stored_key| stored_key.secret_hash == hash_opaque_token(secret))
+    .ok_or_else(|| {
+        ApiKeyError::InvalidKey(anyhow::anyhow!("Unknown, revoked or expired API key."))
+    })?;
+
+    sqlx::query!(
+        r#"UPDATE api_keys SET last_used_at = now() WHERE id = $1"#,
+        stored_key.id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to record the use of the API key.")?;
+    let scopes = Scope::parse_list(&stored_key.scope).map_err(|e| anyhow::anyhow!(e))?;
+    // The role of the owner is read on every use, a demoted owner loses the scopes
+    let user = get_user_access(stored_key.owner_id, pool).await?;
+
+    Ok(user.restrict_scopes(&scopes))
+}
@@ -15,23 +15,28 @@ pub fn decode(token: &str) -> Result<Token, DecodeError> {
         return Err(DecodeError::InvalidTokenFormat);
     }
 
-    let decoder = DECODER
-        .decode(segments[0])
-        .expect("Failed to decode payload string");
-    let header_str = String::from_utf8(decoder).expect("Invalid UTF8 format");
-    let header: Header =
-        serde_json::from_str(&header_str).expect("Failed to parse to Payload struct");
-    let decoder = DECODER
-        .decode(segments[1])
-        .expect("Failed to decode payload string");
-    let payload_str = String::from_utf8(decoder).expect("Invalid UTF8 format");
+    let header_str = decode_segment(segments[0])?;
+    let header: Header = serde_json::from_str(&header_str).map_err(DecodeError::InvalidHeader)?;
+    let payload_str = decode_segment(segments[1])?;
     let payload: Payload =
-        serde_json::from_str(&payload_str).expect("Failed to parse to Payload struct");
-    let token = Token::new(header, payload);
+        serde_json::from_str(&payload_str).map_err(DecodeError::InvalidPayload)?;
+    let signature = DECODER
+        .decode(segments[2])
+        .map_err(DecodeError::InvalidBase64)?;
+    let signing_input = format!("{}{TOKEN_DELIMETER}{}", segments[0], segments[1]);
+    let token = Token::new(header, payload, signing_input, signature);
 
     Ok(token)
 }
 
+fn decode_segment(segment: &str) -> Result<String, DecodeError> {
+    let bytes = DECODER
+        .decode(segment)
+        .map_err(DecodeError::InvalidBase64)?;
+
+    String::from_utf8(bytes).map_err(DecodeError::InvalidUtf8)
+}
+
 #[cfg(test)]
 mod test {
     use crate::authentication::{decode, domain::Payload, get_private_key_pk8, sign};
@@ -41,6 +46,17 @@ mod test {
         let test_cases = vec![
             ("sdfasf.sdfsd", "does not have full three parts"),
             ("sdfasf.sdfsd.sfaf.asfasf", "have more than three parts"),
+            ("!!!.e30.c2ln", "has a header that is not base64"),
+            ("_w.e30.c2ln", "has a header that is not UTF-8"),
+            ("bm90LWpzb24.e30.c2ln", "has a header that is not JSON"),
+            (
+                "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.e30.c2ln",
+                "has a payload without claims",
+            ),
+            (
+                "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.e30.!!!",
+                "has a signature that is not base64",
+            ),
         ];
 
         for (invalid_token, error_message) in test_cases {
@@ -54,10 +70,10 @@ mod test {
         let payload = Payload::new("Tom".into());
         let secret =
             get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
-        let token = sign(&payload, &secret);
+        let token = sign(&payload, &secret).expect("Failed to sign the token");
 
         let decoded_token = decode(&token).expect("Failed to decode the token");
 
-        assert_eq!(decoded_token.payload.name, payload.name);
+        assert_eq!(decoded_token.payload.sub, payload.sub);
     }
 }
@@ -0,0 +1,245 @@
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Executor, PgPool};
+use uuid::Uuid;
+
+use super::{generate_opaque_token, Algorithm, AuthError, DEFAULT_DID_CHALLENGE_TTL};
+
+const DID_KEY_PREFIX: &str = "did:key:";
+// Multibase prefix of base58-btc
+const MULTIBASE_BASE58_BTC: char = 'z';
+// Multicodec prefix of an Ed25519 public key
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// A nonce the holder of the DID has to sign to log in
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct DidChallenge {
+    pub did: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Resolve a `did:key` identifier to the Ed25519 public key it encodes
+///
+/// # Examples
+///
+/// ```
+/// use rush_booking::authentication::resolve_did_key;
+///
+/// let public_key =
+///     resolve_did_key("did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK").unwrap();
+///
+/// assert_eq!(public_key.len(), 32);
+/// ```
+pub fn resolve_did_key(did: &str) -> Result<Vec<u8>, String> {
+    let multibase = did
+        .strip_prefix(DID_KEY_PREFIX)
+        .ok_or_else(|| format!("{} is not a did:key identifier.", did))?;
+    let encoded = multibase
+        .strip_prefix(MULTIBASE_BASE58_BTC)
+        .ok_or_else(|| format!("{} is not base58-btc encoded.", did))?;
+    let bytes = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|_| format!("{} is not base58-btc encoded.", did))?;
+    let public_key = bytes
+        .strip_prefix(&ED25519_MULTICODEC)
+        .ok_or_else(|| format!("{} is not an Ed25519 key.", did))?;
+    if public_key.len() != ED25519_PUBLIC_KEY_LENGTH {
+        return Err(format!("{} is not an Ed25519 key.", did));
+    }
+
+    Ok(public_key.to_vec())
+}
+
+/// Issue a single-use nonce for the given DID.
+///
+/// A challenge is issued whether or not the DID is linked to a user, so that
+/// the endpoint does not tell which DIDs are known.
+#[tracing::instrument(name = "Issue DID challenge", skip(pool))]
+pub async fn issue_did_challenge(did: &str, pool: &PgPool) -> Result<DidChallenge, anyhow::Error> {
+    let nonce = generate_opaque_token();
+    let now = Utc::now();
+    let expires_at = now + Duration::seconds(DEFAULT_DID_CHALLENGE_TTL as i64);
+    sqlx::query!(
+        r#"
+        INSERT INTO did_challenges (id, did, nonce, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        Uuid::new_v4(),
+        did,
+        nonce,
+        expires_at,
+        now,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store the DID challenge.")?;
+
+    Ok(DidChallenge {
+        did: did.to_string(),
+        nonce,
+        expires_at,
+    })
+}
+
+/// Check the signature of a challenge, proving the caller holds the key of the DID.
+///
+/// The signature is the base64url encoded Ed25519 signature of the nonce.
+/// The challenge is used up by the first attempt, whether it succeeds or not.
+#[tracing::instrument(name = "Check DID challenge", skip(signature, pool))]
+pub async fn check_did_challenge(
+    did: &str,
+    nonce: &str,
+    signature: &str,
+    pool: &PgPool,
+) -> Result<(), AuthError> {
+    let public_key =
+        resolve_did_key(did).map_err(|e| AuthError::InvalidCredentials(anyhow::anyhow!(e)))?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let challenge = sqlx::query!(
+        r#"
+        SELECT id, expires_at, used_at
+        FROM did_challenges
+        WHERE did = $1 AND nonce = $2
+        FOR UPDATE
+        "#,
+        did,
+        nonce,
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the DID challenge.")?
+    .ok_or_else(|| AuthError::InvalidCredentials(anyhow::anyhow!("Unknown DID challenge.")))?;
+    if challenge.used_at.is_some() {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "The DID challenge was already used."
+        )));
+    }
+    let query = sqlx::query!(
+        r#"UPDATE did_challenges SET used_at = now() WHERE id = $1"#,
+        challenge.id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to use up the DID challenge.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to use up a DID challenge.")?;
+    if challenge.expires_at < Utc::now() {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "The DID challenge is expired."
+        )));
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .context("The signature is not base64url encoded.")
+        .map_err(AuthError::InvalidCredentials)?;
+    if !Algorithm::EdDsa.verify(&public_key, nonce.as_bytes(), &signature) {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "Invalid signature of the DID challenge."
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check the signature of a challenge and return the user the DID is linked to.
+#[tracing::instrument(name = "Verify DID challenge", skip(signature, pool))]
+pub async fn verify_did_challenge(
+    did: &str,
+    nonce: &str,
+    signature: &str,
+    pool: &PgPool,
+) -> Result<Uuid, AuthError> {
+    check_did_challenge(did, nonce, signature, pool).await?;
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT user_dids.user_id
+        FROM user_dids
+        JOIN users ON users.user_id = user_dids.user_id
+        WHERE user_dids.did = $1 AND users.status = 'active'
+        "#,
+        did
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the user of the DID.")?
+    .ok_or_else(|| AuthError::InvalidCredentials(anyhow::anyhow!("Unknown DID.")))
+}
+
+/// Link a DID to the user, return the user the DID ends up linked to.
+///
+/// A DID already linked to another user stays with that user.
+#[tracing::instrument(name = "Link DID to user", skip(pool))]
+pub async fn link_user_did(user_id: Uuid, did: &str, pool: &PgPool) -> Result<Uuid, anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_dids (did, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (did) DO NOTHING
+        "#,
+        did,
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to link the DID.")?;
+
+    sqlx::query_scalar!(r#"SELECT user_id FROM user_dids WHERE did = $1"#, did)
+        .fetch_one(pool)
+        .await
+        .context("Failed to retrieve the user of the DID.")
+}
+
+#[cfg(test)]
+mod test {
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    fn did_of(public_key: &[u8]) -> String {
+        let mut bytes = ED25519_MULTICODEC.to_vec();
+        bytes.extend_from_slice(public_key);
+        format!("did:key:z{}", bs58::encode(bytes).into_string())
+    }
+
+    #[test]
+    fn should_resolve_the_public_key_of_a_did_key() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let public_key = signing_key.verifying_key();
+
+        let resolved = resolve_did_key(&did_of(public_key.as_bytes())).unwrap();
+
+        assert_eq!(resolved, public_key.as_bytes());
+    }
+
+    #[test]
+    fn should_err_with_dids_that_are_not_ed25519_keys() {
+        let mut secp256k1 = vec![0xe7, 0x01];
+        secp256k1.extend_from_slice(&[1u8; 33]);
+        let test_cases = vec![
+            ("did:web:example.com".to_string(), "is another method"),
+            ("did:key:f6MkhaXgBZ".to_string(), "is not base58-btc"),
+            ("did:key:z0OIl".to_string(), "is not base58"),
+            (
+                format!("did:key:z{}", bs58::encode(secp256k1).into_string()),
+                "is another key type",
+            ),
+            (did_of(&[1u8; 31]), "is too short"),
+        ];
+
+        for (did, error_message) in test_cases {
+            assert!(resolve_did_key(&did).is_err(), "{}", error_message);
+        }
+    }
+}
@@ -1,11 +1,19 @@
 use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Algorithm, Role, Scope};
 
 pub const TOKEN_DELIMETER: &str = ".";
 pub const DEFAULT_TOKEN_ALG: &str = "RS256";
 pub const DEFAULT_TOKEN_TYPE: &str = "JWT";
 pub const DEFAULT_TOKEN_TTL: u64 = 3600; // One hour
+pub const DEFAULT_REFRESH_TOKEN_TTL: u64 = 30 * 24 * 3600; // Thirty days
+pub const DEFAULT_DID_CHALLENGE_TTL: u64 = 300; // Five minutes
+pub const DEFAULT_REGISTRATION_TOKEN_TTL: u64 = 24 * 3600; // One day
+pub const DEFAULT_PASSWORD_RESET_TOKEN_TTL: u64 = 3600; // One hour
+pub const DEFAULT_MFA_CHALLENGE_TTL: u64 = 300; // Five minutes
 
 #[derive(Deserialize, Serialize)]
 pub struct JwtResponse {
@@ -14,13 +22,29 @@ pub struct JwtResponse {
     pub access_token: String,
     pub refresh_token: String,
     pub id_token: String,
-    pub expires_in: u16,
+    pub expires_in: u64,
+}
+
+impl JwtResponse {
+    pub fn bearer(access_token: String, refresh_token: String, scopes: &[Scope]) -> Self {
+        Self {
+            token_type: "Bearer".into(),
+            scope: Scope::join(scopes),
+            access_token,
+            refresh_token,
+            id_token: "id_token".into(),
+            expires_in: DEFAULT_TOKEN_TTL,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct Header {
     pub alg: String,
     pub typ: String,
+    // Names the key the token is signed with, see the JWKS endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
 }
 
 impl Header {
@@ -28,8 +52,25 @@ impl Header {
         Self {
             alg: DEFAULT_TOKEN_ALG.to_string(),
             typ: DEFAULT_TOKEN_TYPE.to_string(),
+            kid: None,
         }
     }
+
+    pub fn set_alg(mut self, alg: Algorithm) -> Self {
+        self.alg = alg.as_ref().to_string();
+        self
+    }
+
+    pub fn set_kid(mut self, kid: &str) -> Self {
+        self.kid = Some(kid.to_string());
+        self
+    }
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// See [the RFC 7519] for more.
@@ -37,15 +78,32 @@ impl Header {
 /// [the link]: https://www.rfc-editor.org/rfc/rfc7519
 #[derive(Deserialize, Serialize)]
 pub struct Payload {
-    pub name: String,
-    pub exp: u64, // 2. Terminology - NumericDate
+    pub sub: String, // 4.1.2. "sub" (Subject) Claim
+    #[serde(default)]
+    pub iss: String, // 4.1.1. "iss" (Issuer) Claim
+    #[serde(default)]
+    pub aud: String, // 4.1.3. "aud" (Audience) Claim
+    pub exp: u64,    // 2. Terminology - NumericDate
+    // Private claims, a token without them grants the least privileges
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub host_ids: Vec<Uuid>,
+    // Space-delimited, see RFC 8693 4.2. "scope" (Scopes) Claim
+    #[serde(default)]
+    pub scope: String,
 }
 
 impl Payload {
-    pub fn new(name: String) -> Self {
+    pub fn new(sub: String) -> Self {
         Self {
-            name,
+            sub,
+            iss: String::new(),
+            aud: String::new(),
             exp: get_expired_unix_timestamp(DEFAULT_TOKEN_TTL),
+            role: Role::default(),
+            host_ids: Vec::new(),
+            scope: String::new(),
         }
     }
 
@@ -53,11 +111,35 @@ impl Payload {
         self.exp = get_expired_unix_timestamp(ttl.as_secs());
         self
     }
+
+    pub fn set_issuer(mut self, iss: &str) -> Self {
+        self.iss = iss.to_string();
+        self
+    }
+
+    pub fn set_audience(mut self, aud: &str) -> Self {
+        self.aud = aud.to_string();
+        self
+    }
+
+    pub fn set_role(mut self, role: Role, host_ids: Vec<Uuid>) -> Self {
+        self.role = role;
+        self.host_ids = host_ids;
+        self
+    }
+
+    pub fn set_scope(mut self, scopes: &[Scope]) -> Self {
+        self.scope = Scope::join(scopes);
+        self
+    }
 }
 
 pub struct Token {
     pub header: Header,
     pub payload: Payload,
+    // The encoded `header.payload` as received, it is what the signature covers
+    pub signing_input: String,
+    pub signature: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -68,11 +150,23 @@ pub enum TokenError {
     Expired,
     MissingRequiredClaims,
     InvalidIssuer,
+    InvalidAudience,
+    UnknownKey,
 }
 
 impl Token {
-    pub fn new(header: Header, payload: Payload) -> Self {
-        Self { header, payload }
+    pub fn new(
+        header: Header,
+        payload: Payload,
+        signing_input: String,
+        signature: Vec<u8>,
+    ) -> Self {
+        Self {
+            header,
+            payload,
+            signing_input,
+            signature,
+        }
     }
 
     pub fn is_expired(&self) -> bool {
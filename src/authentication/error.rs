@@ -2,11 +2,19 @@
 pub enum MyError {
     IO(std::io::Error),
     BadPrivateKey,
-    OOM,
+    BadPublicKey,
+    MissingSigningKey,
+    Oom,
     BadSignature,
+    UnsupportedAlgorithm,
+    Serialization(serde_json::Error),
 }
 
 #[derive(Debug)]
 pub enum DecodeError {
     InvalidTokenFormat,
+    InvalidBase64(base64::DecodeError),
+    InvalidUtf8(std::string::FromUtf8Error),
+    InvalidHeader(serde_json::Error),
+    InvalidPayload(serde_json::Error),
 }
@@ -0,0 +1,179 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use super::{error::MyError, Algorithm};
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_INTEGER: u8 = 0x02;
+
+/// A JSON Web Key Set, see [RFC 7517 5]
+///
+/// [RFC 7517 5]: https://www.rfc-editor.org/rfc/rfc7517#section-5
+#[derive(Deserialize, Serialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+/// The public half of one of our signing keys, see [RFC 7518 6.3.1] for RSA
+/// and [RFC 8037 2] for Ed25519
+///
+/// [RFC 7518 6.3.1]: https://www.rfc-editor.org/rfc/rfc7518#section-6.3.1
+/// [RFC 8037 2]: https://www.rfc-editor.org/rfc/rfc8037#section-2
+#[derive(Deserialize, Serialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(rename = "use")]
+    pub use_: String,
+    pub alg: String,
+    pub kid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+}
+
+impl Jwk {
+    /// Build the JWK of a PKCS#1 `RSAPublicKey` in DER, as written by `scripts/init_jwt_keypair.sh`
+    pub fn from_rsa_public_key_der(kid: &str, der: &[u8]) -> Result<Self, MyError> {
+        let (public_key, _) = read_der(der, DER_SEQUENCE)?;
+        let (modulus, rest) = read_der(public_key, DER_INTEGER)?;
+        let (exponent, _) = read_der(rest, DER_INTEGER)?;
+
+        Ok(Self {
+            kty: "RSA".into(),
+            use_: "sig".into(),
+            alg: Algorithm::Rs256.as_ref().into(),
+            kid: kid.into(),
+            n: Some(URL_SAFE_NO_PAD.encode(unsigned(modulus))),
+            e: Some(URL_SAFE_NO_PAD.encode(unsigned(exponent))),
+            crv: None,
+            x: None,
+        })
+    }
+
+    /// Build the JWK of a raw Ed25519 public key
+    pub fn from_ed25519_public_key(kid: &str, public_key: &[u8]) -> Result<Self, MyError> {
+        Algorithm::EdDsa.check_public_key(public_key)?;
+
+        Ok(Self {
+            kty: "OKP".into(),
+            use_: "sig".into(),
+            alg: Algorithm::EdDsa.as_ref().into(),
+            kid: kid.into(),
+            n: None,
+            e: None,
+            crv: Some("Ed25519".into()),
+            x: Some(URL_SAFE_NO_PAD.encode(public_key)),
+        })
+    }
+
+    pub fn from_public_key(kid: &str, alg: Algorithm, public_key: &[u8]) -> Result<Self, MyError> {
+        match alg {
+            Algorithm::Rs256 => Self::from_rsa_public_key_der(kid, public_key),
+            Algorithm::EdDsa => Self::from_ed25519_public_key(kid, public_key),
+        }
+    }
+}
+
+// Read one DER element with the expected tag, returns its value and what follows it
+fn read_der(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), MyError> {
+    match input {
+        [t, first, rest @ ..] if *t == tag => {
+            let (length, rest) = match *first {
+                // Short form, the length fits in the first byte
+                length if length < 0x80 => (length as usize, rest),
+                // Long form, the first byte is the number of length bytes
+                0x81..=0x84 => {
+                    let size = (first & 0x7f) as usize;
+                    if rest.len() < size {
+                        return Err(MyError::BadPublicKey);
+                    }
+                    let length = rest[..size]
+                        .iter()
+                        .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+                    (length, &rest[size..])
+                }
+                _ => return Err(MyError::BadPublicKey),
+            };
+            if rest.len() < length {
+                return Err(MyError::BadPublicKey);
+            }
+
+            Ok(rest.split_at(length))
+        }
+        _ => Err(MyError::BadPublicKey),
+    }
+}
+
+// DER integers are signed, JWK wants the unsigned big-endian bytes
+fn unsigned(integer: &[u8]) -> &[u8] {
+    match integer {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => integer,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ring::rsa::KeyPair;
+
+    use super::*;
+    use crate::authentication::{get_private_key_pk8, get_public_key_der};
+
+    #[test]
+    fn should_encode_the_public_key_of_the_key_pair() {
+        let private_key =
+            get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
+        let public_key =
+            get_public_key_der("./public-key.der").expect("Failed to retrieve the public key");
+        let key_pair = KeyPair::from_pkcs8(&private_key).unwrap();
+
+        assert_eq!(public_key, key_pair.public().as_ref());
+
+        let jwk = Jwk::from_rsa_public_key_der("primary", &public_key).unwrap();
+
+        let modulus = URL_SAFE_NO_PAD.decode(jwk.n.unwrap()).unwrap();
+        assert_eq!(modulus.len(), key_pair.public().modulus_len());
+        assert_ne!(modulus[0], 0);
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(jwk.e.unwrap()).unwrap(),
+            vec![1, 0, 1]
+        );
+        assert_eq!(jwk.kid, "primary");
+    }
+
+    #[test]
+    fn should_err_with_invalid_der() {
+        let test_cases = vec![
+            (vec![], "is empty"),
+            (vec![0x02, 0x01, 0x01], "is not a sequence"),
+            (vec![0x30, 0x05, 0x02, 0x01], "is truncated"),
+            (vec![0x30, 0x03, 0x02, 0x01, 0x01], "has no exponent"),
+        ];
+
+        for (der, error_message) in test_cases {
+            assert!(
+                Jwk::from_rsa_public_key_der("primary", &der).is_err(),
+                "{}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn should_encode_a_raw_ed25519_public_key() {
+        let public_key = [7u8; 32];
+
+        let jwk = Jwk::from_ed25519_public_key("edge", &public_key).unwrap();
+
+        assert_eq!(jwk.kty, "OKP");
+        assert_eq!(jwk.alg, "EdDSA");
+        assert_eq!(jwk.crv.as_deref(), Some("Ed25519"));
+        assert_eq!(URL_SAFE_NO_PAD.decode(jwk.x.unwrap()).unwrap(), public_key);
+        assert!(Jwk::from_ed25519_public_key("edge", &[7u8; 31]).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::configuration::JwtSettings;
+
+use super::{
+    error::MyError, get_private_key_pk8, get_public_key_der, sign_with_header, Algorithm,
+    AuthenticatedUser, Header, Jwk, JwkSet, Payload,
+};
+
+/// Keys and claims used to sign and verify access tokens, loaded once at startup.
+///
+/// Only one key signs new tokens, retired keys are kept to verify the tokens
+/// they signed until those expire.
+pub struct JwtKeys {
+    signing_kid: String,
+    signing_alg: Algorithm,
+    private_key: Vec<u8>,
+    public_keys: Vec<VerificationKey>,
+    issuer: String,
+    audience: String,
+}
+
+struct VerificationKey {
+    kid: String,
+    alg: Algorithm,
+    public_key: Vec<u8>,
+}
+
+impl JwtKeys {
+    pub fn new(
+        kid: &str,
+        alg: Algorithm,
+        private_key: Vec<u8>,
+        public_key: Vec<u8>,
+        issuer: &str,
+        audience: &str,
+    ) -> Self {
+        Self {
+            signing_kid: kid.to_string(),
+            signing_alg: alg,
+            private_key,
+            public_keys: vec![VerificationKey {
+                kid: kid.to_string(),
+                alg,
+                public_key,
+            }],
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+        }
+    }
+
+    /// Keep accepting the tokens signed by a retired key
+    pub fn add_verification_key(mut self, kid: &str, alg: Algorithm, public_key: Vec<u8>) -> Self {
+        self.public_keys.push(VerificationKey {
+            kid: kid.to_string(),
+            alg,
+            public_key,
+        });
+        self
+    }
+
+    pub fn from_settings(settings: &JwtSettings) -> Result<Self, MyError> {
+        let (signing_key, retired_keys) = settings
+            .keys
+            .split_first()
+            .ok_or(MyError::MissingSigningKey)?;
+        let private_key_path = signing_key
+            .private_key_path
+            .as_deref()
+            .ok_or(MyError::MissingSigningKey)?;
+        let mut keys = Self::new(
+            &signing_key.kid,
+            signing_key.alg,
+            get_private_key_pk8(private_key_path)?,
+            get_public_key_der(&signing_key.public_key_path)?,
+            &settings.issuer,
+            &settings.audience,
+        );
+        for key in retired_keys {
+            keys = keys.add_verification_key(
+                &key.kid,
+                key.alg,
+                get_public_key_der(&key.public_key_path)?,
+            );
+        }
+        // Fail at startup rather than at the first login or JWKS request
+        keys.signing_alg.sign(&keys.private_key, b"")?;
+        keys.jwks()?;
+
+        Ok(keys)
+    }
+
+    /// Payload of a token issued by us for the given subject
+    pub fn new_payload(&self, sub: String) -> Payload {
+        Payload::new(sub)
+            .set_issuer(&self.issuer)
+            .set_audience(&self.audience)
+    }
+
+    /// Sign an access token carrying the role of the user
+    pub fn access_token(&self, user: &AuthenticatedUser) -> Result<String, MyError> {
+        let payload = self
+            .new_payload(user.user_id.to_string())
+            .set_role(user.role, user.host_ids.clone())
+            .set_scope(&user.scopes);
+
+        sign_with_header(
+            &Header::new()
+                .set_alg(self.signing_alg)
+                .set_kid(&self.signing_kid),
+            &payload,
+            &self.private_key,
+        )
+    }
+
+    /// Public keys, with their algorithm, that may have signed a token with the given `kid`.
+    ///
+    /// Tokens issued before keys were named carry no `kid`, every key is a candidate.
+    pub fn verification_keys(&self, kid: Option<&str>) -> Vec<(Algorithm, &[u8])> {
+        self.public_keys
+            .iter()
+            .filter(|key| match kid {
+                Some(kid) => kid == key.kid,
+                None => true,
+            })
+            .map(|key| (key.alg, key.public_key.as_slice()))
+            .collect()
+    }
+
+    pub fn jwks(&self) -> Result<JwkSet, MyError> {
+        let keys = self
+            .public_keys
+            .iter()
+            .map(|key| Jwk::from_public_key(&key.kid, key.alg, &key.public_key))
+            .collect::<Result<Vec<Jwk>, MyError>>()?;
+
+        Ok(JwkSet { keys })
+    }
+
+    pub fn signing_kid(&self) -> &str {
+        &self.signing_kid
+    }
+
+    pub fn signing_alg(&self) -> Algorithm {
+        self.signing_alg
+    }
+
+    pub fn private_key(&self) -> &[u8] {
+        &self.private_key
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        authentication::{decode, verify},
+        configuration::JwtKeySettings,
+    };
+
+    #[test]
+    fn should_sign_with_the_algorithm_of_the_first_key() {
+        let private_key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let public_key = Ed25519KeyPair::from_pkcs8(private_key.as_ref())
+            .unwrap()
+            .public_key()
+            .as_ref()
+            .to_vec();
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let private_key_path = dir.join("private-key.pk8");
+        let public_key_path = dir.join("public-key.der");
+        std::fs::write(&private_key_path, private_key.as_ref()).unwrap();
+        std::fs::write(&public_key_path, public_key).unwrap();
+        let settings = JwtSettings {
+            issuer: "rush_booking".into(),
+            audience: "rush_booking_api".into(),
+            keys: vec![
+                JwtKeySettings {
+                    kid: "edge".into(),
+                    alg: Algorithm::EdDsa,
+                    private_key_path: Some(private_key_path.to_string_lossy().into()),
+                    public_key_path: public_key_path.to_string_lossy().into(),
+                },
+                JwtKeySettings {
+                    kid: "primary".into(),
+                    alg: Algorithm::Rs256,
+                    private_key_path: None,
+                    public_key_path: "./public-key.der".into(),
+                },
+            ],
+        };
+
+        let keys = JwtKeys::from_settings(&settings).expect("Failed to load the keys");
+        let user = AuthenticatedUser {
+            user_id: Uuid::new_v4(),
+            role: Default::default(),
+            host_ids: vec![],
+            scopes: vec![],
+        };
+        let token = decode(&keys.access_token(&user).unwrap()).unwrap();
+
+        assert_eq!(keys.signing_alg(), Algorithm::EdDsa);
+        assert_eq!(token.header.alg, "EdDSA");
+        assert_eq!(token.header.kid.as_deref(), Some("edge"));
+        assert!(verify(&token, &keys).is_ok());
+        assert_eq!(keys.jwks().unwrap().keys.len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::configuration::LoginThrottleSettings;
+
+/// What failed logins are counted against
+#[derive(Debug)]
+pub enum LoginAttemptKey {
+    Account(String),
+    Ip(String),
+}
+
+impl LoginAttemptKey {
+    fn key(&self) -> String {
+        match self {
+            LoginAttemptKey::Account(username) => format!("account:{}", username.to_lowercase()),
+            LoginAttemptKey::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+
+    fn free_attempts(&self, settings: &LoginThrottleSettings) -> u32 {
+        match self {
+            LoginAttemptKey::Account(_) => settings.account_free_attempts,
+            LoginAttemptKey::Ip(_) => settings.ip_free_attempts,
+        }
+    }
+}
+
+/// Lockout after the given number of failed logins, `None` while there are free attempts left
+///
+/// # Examples
+///
+/// ```
+/// use rush_booking::authentication::lockout_duration;
+///
+/// assert_eq!(lockout_duration(4, 5, 30, 3600), None);
+/// assert_eq!(lockout_duration(5, 5, 30, 3600), Some(30));
+/// assert_eq!(lockout_duration(7, 5, 30, 3600), Some(120));
+/// assert_eq!(lockout_duration(50, 5, 30, 3600), Some(3600));
+/// ```
+pub fn lockout_duration(
+    failed_attempts: u32,
+    free_attempts: u32,
+    base_lockout_seconds: u64,
+    max_lockout_seconds: u64,
+) -> Option<u64> {
+    let exponent = failed_attempts.checked_sub(free_attempts)?;
+    let lockout = 2u64
+        .checked_pow(exponent)
+        .and_then(|factor| factor.checked_mul(base_lockout_seconds))
+        .unwrap_or(max_lockout_seconds);
+
+    Some(lockout.min(max_lockout_seconds))
+}
+
+/// Time left until the latest lockout of the given keys ends, `None` when none is locked out
+#[tracing::instrument(name = "Check login lockout", skip(pool))]
+pub async fn check_login_lockout(
+    keys: &[LoginAttemptKey],
+    pool: &PgPool,
+) -> Result<Option<Duration>, anyhow::Error> {
+    let keys = keys.iter().map(LoginAttemptKey::key).collect::<Vec<_>>();
+    let locked_until = sqlx::query_scalar!(
+        r#"
+        SELECT MAX(locked_until)
+        FROM login_attempts
+        WHERE key = ANY($1) AND locked_until > now()
+        "#,
+        &keys,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to retrieve the login lockouts.")?;
+
+    Ok(locked_until.map(|locked_until| locked_until - Utc::now()))
+}
+
+/// Count a failed login against every key and lock out the ones out of free attempts
+#[tracing::instrument(name = "Record failed login", skip(settings, pool))]
+pub async fn record_failed_login(
+    keys: &[LoginAttemptKey],
+    settings: &LoginThrottleSettings,
+    pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    for key in keys {
+        let failed_attempts = sqlx::query_scalar!(
+            r#"
+            INSERT INTO login_attempts (key, failed_attempts, last_failed_at)
+            VALUES ($1, 1, now())
+            ON CONFLICT (key) DO UPDATE
+            SET failed_attempts = CASE
+                    WHEN login_attempts.last_failed_at < now() - make_interval(secs => $2) THEN 1
+                    ELSE login_attempts.failed_attempts + 1
+                END,
+                last_failed_at = now()
+            RETURNING failed_attempts
+            "#,
+            key.key(),
+            settings.reset_after_seconds as f64,
+        )
+        .fetch_one(pool)
+        .await
+        .context("Failed to record a failed login.")?;
+
+        let lockout = lockout_duration(
+            failed_attempts as u32,
+            key.free_attempts(settings),
+            settings.base_lockout_seconds,
+            settings.max_lockout_seconds,
+        );
+        if let Some(lockout) = lockout {
+            let locked_until: DateTime<Utc> = Utc::now() + Duration::seconds(lockout as i64);
+            sqlx::query!(
+                r#"UPDATE login_attempts SET locked_until = $1 WHERE key = $2"#,
+                locked_until,
+                key.key(),
+            )
+            .execute(pool)
+            .await
+            .context("Failed to lock out logins.")?;
+            tracing::warn!(key = %key.key(), %locked_until, "Logins are locked out");
+        }
+    }
+
+    Ok(())
+}
+
+/// Forget the failed logins of the key, after a successful login to the account
+#[tracing::instrument(name = "Clear failed logins", skip(pool))]
+pub async fn clear_failed_logins(
+    key: &LoginAttemptKey,
+    pool: &PgPool,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(r#"DELETE FROM login_attempts WHERE key = $1"#, key.key())
+        .execute(pool)
+        .await
+        .context("Failed to clear the failed logins.")?;
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::header::{self, ContentType},
+    middleware::Next,
+    web, HttpMessage, HttpResponse,
+};
+use anyhow::anyhow;
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{authenticate_api_key, decode, verify, JwtKeys, Role, Scope};
+use crate::utils::ResponseData;
+
+const BEARER_PREFIX: &str = "Bearer ";
+const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// The subject of a verified access token, or the owner of an API key.
+///
+/// It is inserted in the request extensions by [`reject_anonymous_users`],
+/// handlers read it with `web::ReqData<AuthenticatedUser>`.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub role: Role,
+    pub host_ids: Vec<Uuid>,
+    pub scopes: Vec<Scope>,
+}
+
+impl AuthenticatedUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Whether the user was granted every scope of the role
+    pub fn has_full_scope(&self) -> bool {
+        self.role
+            .scopes()
+            .iter()
+            .all(|scope| self.scopes.contains(scope))
+    }
+
+    /// Keep only the granted scopes that were requested
+    pub fn restrict_scopes(mut self, requested: &[Scope]) -> Self {
+        self.scopes.retain(|scope| requested.contains(scope));
+        self
+    }
+
+    pub fn can_manage_host(&self, host_id: Uuid) -> bool {
+        match self.role {
+            Role::Admin => true,
+            Role::HostManager => self.host_ids.contains(&host_id),
+            Role::Guest => false,
+        }
+    }
+
+    /// The hosts the user may see, `None` for every host
+    pub fn managed_host_ids(&self) -> Option<&[Uuid]> {
+        match self.role {
+            Role::Admin => None,
+            Role::HostManager => Some(&self.host_ids),
+            Role::Guest => Some(&[]),
+        }
+    }
+}
+
+/// Authenticate with an access token, `Authorization: Bearer <token>`, or
+/// with an API key, `X-Api-Key: <key>`
+pub async fn reject_anonymous_users(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let user = match api_key(&req).map_err(unauthorized)? {
+        Some(key) => authenticate_with_api_key(&req, &key).await,
+        None => authenticate(&req),
+    }
+    .map_err(unauthorized)?;
+    req.extensions_mut().insert(user);
+
+    next.call(req).await
+}
+
+/// Only the account owner may manage the account, with an access token that
+/// was granted every scope of the role. API keys and restricted sessions are
+/// rejected, so they cannot be turned into a full session.
+pub async fn reject_delegated_credentials(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if api_key(&req).map_err(unauthorized)?.is_some() {
+        return Err(unauthorized(anyhow!("API keys cannot manage the account")));
+    }
+    let user = authenticate(&req).map_err(unauthorized)?;
+    if !user.has_full_scope() {
+        return Err(forbidden(anyhow!(
+            "A session with restricted scopes cannot manage the account"
+        )));
+    }
+    req.extensions_mut().insert(user);
+
+    next.call(req).await
+}
+
+/// Only staff may enter the admin area, it must run after [`reject_anonymous_users`]
+pub async fn reject_guests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let role = req
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|user| user.role)
+        .unwrap_or_default();
+    if role == Role::Guest {
+        return Err(forbidden(anyhow!("Guests cannot access the admin area")));
+    }
+
+    next.call(req).await
+}
+
+fn authenticate(req: &ServiceRequest) -> Result<AuthenticatedUser, anyhow::Error> {
+    let keys = req
+        .app_data::<web::Data<JwtKeys>>()
+        .ok_or_else(|| anyhow!("The JWT keys are not configured"))?;
+    let token = bearer_token(req)?;
+    let token = decode(token).map_err(|e| anyhow!("Failed to decode the token: {:?}", e))?;
+    verify(&token, keys).map_err(|e| anyhow!("Failed to verify the token: {:?}", e))?;
+    let user_id = Uuid::parse_str(&token.payload.sub)
+        .map_err(|_| anyhow!("The subject of the token is not a user id"))?;
+    let scopes = Scope::parse_list(&token.payload.scope).map_err(|e| anyhow!(e))?;
+
+    Ok(AuthenticatedUser {
+        user_id,
+        role: token.payload.role,
+        host_ids: token.payload.host_ids,
+        scopes,
+    })
+}
+
+async fn authenticate_with_api_key(
+    req: &ServiceRequest,
+    key: &Secret<String>,
+) -> Result<AuthenticatedUser, anyhow::Error> {
+    let pool = req
+        .app_data::<web::Data<PgPool>>()
+        .ok_or_else(|| anyhow!("The database pool is not configured"))?;
+
+    Ok(authenticate_api_key(key, pool).await?)
+}
+
+fn api_key(req: &ServiceRequest) -> Result<Option<Secret<String>>, anyhow::Error> {
+    req.headers()
+        .get(API_KEY_HEADER)
+        .map(|key| {
+            key.to_str()
+                .map(|key| Secret::new(key.to_string()))
+                .map_err(|_| anyhow!("The '{}' header is not a valid string", API_KEY_HEADER))
+        })
+        .transpose()
+}
+
+fn bearer_token(req: &ServiceRequest) -> Result<&str, anyhow::Error> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| anyhow!("The 'Authorization' header is missing"))?
+        .to_str()
+        .map_err(|_| anyhow!("The 'Authorization' header is not a valid string"))?
+        .strip_prefix(BEARER_PREFIX)
+        .ok_or_else(|| anyhow!("The authorization scheme is not 'Bearer'"))
+}
+
+fn unauthorized(e: anyhow::Error) -> actix_web::Error {
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::UNAUTHORIZED.as_u16(),
+        message: "Authentication required".to_string(),
+    };
+    let response = HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+        .content_type(ContentType::json())
+        .json(data);
+
+    InternalError::from_response(e, response).into()
+}
+
+pub(super) fn forbidden(e: anyhow::Error) -> actix_web::Error {
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::FORBIDDEN.as_u16(),
+        message: "Permission denied".to_string(),
+    };
+    let response = HttpResponse::Forbidden()
+        .content_type(ContentType::json())
+        .json(data);
+
+    InternalError::from_response(e, response).into()
+}
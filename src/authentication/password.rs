@@ -4,9 +4,13 @@ use argon2::{
     PasswordVerifier, Version,
 };
 use secrecy::{ExposeSecret, Secret};
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
 
-use crate::{domain::CustomerEmail, telemetry::spawn_blocking_with_tracing};
+use super::generate_opaque_token;
+use crate::{
+    configuration::PasswordHashingSettings, domain::CustomerEmail,
+    telemetry::spawn_blocking_with_tracing,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum AuthError {
@@ -16,40 +20,72 @@ pub enum AuthError {
     UnexpectedError(#[from] anyhow::Error),
 }
 
+/// Hash verified in place of the stored one when the username is unknown.
+///
+/// It is computed at startup with the current settings, so that unknown
+/// usernames take as long to check as known ones.
+pub struct DummyPasswordHash(Secret<String>);
+
+impl DummyPasswordHash {
+    pub fn compute(hashing: &PasswordHashingSettings) -> Result<Self, anyhow::Error> {
+        let password = Secret::new(generate_opaque_token());
+        compute_password_hash(password, hashing).map(Self)
+    }
+}
+
 pub struct Credentials {
     pub username: CustomerEmail,
     pub password: Secret<String>,
 }
 
-#[tracing::instrument(name = "Validate credentials", skip(credentials, pool))]
+/// Check the credentials and return the id of the user.
+///
+/// A password hash computed with weaker parameters than the current ones is
+/// upgraded on the way, so that costs can be raised without a password reset.
+#[tracing::instrument(
+    name = "Validate credentials",
+    skip(credentials, hashing, dummy_password_hash, pool)
+)]
 pub async fn validate_credentials(
     credentials: Credentials,
+    hashing: &PasswordHashingSettings,
+    dummy_password_hash: &DummyPasswordHash,
     pool: &PgPool,
 ) -> Result<uuid::Uuid, AuthError> {
     let mut user_id = None;
     // Prevent timing attack exploit
-    let mut expected_password_hash = Secret::new(
-        "$argon2id$v=19$m=15000,t=2,p=1$\
-        gZiV/M1gPc22ElAH/Jh1Hw$\
-        CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno"
-            .to_string(),
-    );
+    let mut expected_password_hash = dummy_password_hash.0.clone();
     if let Some((stored_user_id, stored_password_hash)) =
-        get_stored_credentials(pool, &credentials.username.as_ref()).await?
+        get_stored_credentials(pool, credentials.username.as_ref()).await?
     {
         user_id = Some(stored_user_id);
         expected_password_hash = stored_password_hash;
     }
 
-    spawn_blocking_with_tracing(move || {
-        verify_password_hash(expected_password_hash, credentials.password)
+    let hashing = hashing.clone();
+    let upgraded_password_hash = spawn_blocking_with_tracing(move || {
+        verify_password_hash(&expected_password_hash, &credentials.password)?;
+        if !needs_rehash(expected_password_hash.expose_secret(), &hashing) {
+            return Ok(None);
+        }
+        compute_password_hash(credentials.password, &hashing)
+            .map(Some)
+            .map_err(AuthError::UnexpectedError)
     })
     .await
     .context("Failed to spawn blocking task.")??;
 
-    user_id
+    let user_id = user_id
         .ok_or_else(|| anyhow::anyhow!("Unknown username."))
-        .map_err(AuthError::InvalidCredentials)
+        .map_err(AuthError::InvalidCredentials)?;
+    if let Some(password_hash) = upgraded_password_hash {
+        // The login goes on with the old hash if the upgrade fails
+        if let Err(e) = store_password_hash(user_id, password_hash, pool).await {
+            tracing::warn!(error.cause_chain = ?e, "Failed to upgrade the password hash");
+        }
+    }
+
+    Ok(user_id)
 }
 
 #[tracing::instrument(name = "Get stored credentials", skip(username, pool))]
@@ -61,7 +97,7 @@ async fn get_stored_credentials(
         r#"
         SELECT user_id, password_hash
         FROM users
-        WHERE username = $1
+        WHERE username = $1 AND status = 'active'
         "#,
         username,
     )
@@ -78,10 +114,10 @@ async fn get_stored_credentials(
     skip(password_candidate, expected_password_hash)
 )]
 fn verify_password_hash(
-    expected_password_hash: Secret<String>,
-    password_candidate: Secret<String>,
+    expected_password_hash: &Secret<String>,
+    password_candidate: &Secret<String>,
 ) -> Result<(), AuthError> {
-    let expected_password_hash = PasswordHash::new(&expected_password_hash.expose_secret())
+    let expected_password_hash = PasswordHash::new(expected_password_hash.expose_secret())
         .context("Failed to parse hash in PHC string format")?;
     Argon2::default()
         .verify_password(
@@ -92,15 +128,72 @@ fn verify_password_hash(
         .map_err(AuthError::InvalidCredentials)
 }
 
-#[tracing::instrument(name = "Change password", skip(user_id, password, pool))]
-pub async fn change_password(
+#[tracing::instrument(name = "Get username", skip(pool))]
+pub async fn get_username(user_id: uuid::Uuid, pool: &PgPool) -> Result<String, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT username
+        FROM users
+        WHERE user_id = $1
+        "#,
+        user_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to perform a query to retrieve a username.")?;
+
+    Ok(row.username)
+}
+
+/// Check the password of a logged in user, before a change to the account
+#[tracing::instrument(
+    name = "Verify current password",
+    skip(password, hashing, dummy_password_hash, pool)
+)]
+pub async fn verify_current_password(
     user_id: uuid::Uuid,
     password: Secret<String>,
+    hashing: &PasswordHashingSettings,
+    dummy_password_hash: &DummyPasswordHash,
     pool: &PgPool,
+) -> Result<(), AuthError> {
+    let username = get_username(user_id, pool).await?;
+    let credentials = Credentials {
+        username: CustomerEmail::parse(username).map_err(|e| anyhow::anyhow!(e))?,
+        password,
+    };
+    if validate_credentials(credentials, hashing, dummy_password_hash, pool).await? != user_id {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "The credentials belong to another user."
+        )));
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Change password",
+    skip(transaction, user_id, password, hashing)
+)]
+pub async fn change_password(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: uuid::Uuid,
+    password: Secret<String>,
+    hashing: &PasswordHashingSettings,
+) -> Result<(), anyhow::Error> {
+    let hashing = hashing.clone();
+    let password_hash =
+        spawn_blocking_with_tracing(move || compute_password_hash(password, &hashing))
+            .await?
+            .context("Failed to hash password")?;
+    store_password_hash(user_id, password_hash, &mut **transaction).await
+}
+
+async fn store_password_hash(
+    user_id: uuid::Uuid,
+    password_hash: Secret<String>,
+    executor: impl PgExecutor<'_>,
 ) -> Result<(), anyhow::Error> {
-    let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
-        .await?
-        .context("Failed to hash password")?;
     sqlx::query!(
         r#"
         UPDATE users
@@ -110,21 +203,122 @@ pub async fn change_password(
         password_hash.expose_secret(),
         user_id
     )
-    .execute(pool)
+    .execute(executor)
     .await
     .context("Failed to perform a query to update auth credentials.")?;
 
     Ok(())
 }
 
-fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>, anyhow::Error> {
+pub fn compute_password_hash(
+    password: Secret<String>,
+    hashing: &PasswordHashingSettings,
+) -> Result<Secret<String>, anyhow::Error> {
     let salt = SaltString::generate(&mut rand::thread_rng());
-    let password_hash = Argon2::new(
-        Algorithm::Argon2id,
-        Version::V0x13,
-        Params::new(15000, 2, 1, None).unwrap(),
-    )
-    .hash_password(password.expose_secret().as_bytes(), &salt)?
-    .to_string();
+    let password_hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, hashing.params()?)
+        .hash_password(password.expose_secret().as_bytes(), &salt)?
+        .to_string();
     Ok(Secret::new(password_hash))
 }
+
+/// Whether the PHC string was computed with another algorithm, or weaker
+/// parameters, than the current ones
+///
+/// # Examples
+///
+/// ```
+/// use rush_booking::{authentication::needs_rehash, configuration::PasswordHashingSettings};
+///
+/// let hashing = PasswordHashingSettings {
+///     m_cost: 19456,
+///     t_cost: 2,
+///     p_cost: 1,
+/// };
+///
+/// assert!(needs_rehash(
+///     "$argon2id$v=19$m=15000,t=2,p=1$gZiV/M1gPc22ElAH/Jh1Hw$CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno",
+///     &hashing
+/// ));
+/// ```
+pub fn needs_rehash(password_hash: &str, hashing: &PasswordHashingSettings) -> bool {
+    let Ok(password_hash) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+    let Ok(params) = Params::try_from(&password_hash) else {
+        return true;
+    };
+
+    password_hash.algorithm != Algorithm::Argon2id.ident()
+        || password_hash.version != Some(Version::V0x13.into())
+        || params.m_cost() < hashing.m_cost
+        || params.t_cost() < hashing.t_cost
+        || params.p_cost() < hashing.p_cost
+}
+
+#[cfg(test)]
+mod test {
+    use secrecy::{ExposeSecret, Secret};
+
+    use super::{compute_password_hash, needs_rehash, DummyPasswordHash};
+    use crate::configuration::PasswordHashingSettings;
+
+    fn hashing(m_cost: u32, t_cost: u32, p_cost: u32) -> PasswordHashingSettings {
+        PasswordHashingSettings {
+            m_cost,
+            t_cost,
+            p_cost,
+        }
+    }
+
+    #[test]
+    fn should_rehash_weaker_or_foreign_hashes() {
+        let current = hashing(8192, 2, 1);
+        let test_cases = vec![
+            (hashing(4096, 2, 1), "has less memory"),
+            (hashing(8192, 1, 1), "has fewer iterations"),
+        ];
+
+        for (stored, error_message) in test_cases {
+            let password_hash =
+                compute_password_hash(Secret::new("password".to_string()), &stored).unwrap();
+
+            assert!(
+                needs_rehash(password_hash.expose_secret(), &current),
+                "{}",
+                error_message
+            );
+        }
+        assert!(needs_rehash(
+            "$argon2i$v=19$m=8192,t=2,p=1$gZiV/M1gPc22ElAH/Jh1Hw$CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno",
+            &current
+        ));
+        assert!(needs_rehash("$2b$12$not-an-argon2-hash", &current));
+    }
+
+    #[test]
+    fn should_keep_hashes_as_strong_as_the_current_ones() {
+        let current = hashing(8192, 2, 1);
+        let test_cases = vec![hashing(8192, 2, 1), hashing(16384, 3, 1)];
+
+        for stored in test_cases {
+            let password_hash =
+                compute_password_hash(Secret::new("password".to_string()), &stored).unwrap();
+
+            assert!(!needs_rehash(password_hash.expose_secret(), &current));
+        }
+    }
+
+    #[test]
+    fn dummy_hash_is_computed_with_the_current_settings() {
+        let test_cases = vec![hashing(8192, 2, 1), hashing(16384, 3, 1)];
+
+        for current in test_cases {
+            let dummy_password_hash = DummyPasswordHash::compute(&current).unwrap();
+
+            assert!(!needs_rehash(
+                dummy_password_hash.0.expose_secret(),
+                &current
+            ));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::{generate_opaque_token, hash_opaque_token, DEFAULT_PASSWORD_RESET_TOKEN_TTL};
+
+#[derive(thiserror::Error, Debug)]
+pub enum PasswordResetError {
+    #[error("Invalid password reset token.")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// Issue a password reset token for the active user with the given username.
+///
+/// Returns `None` when there is no such user, callers must not tell it apart
+/// from a sent email.
+#[tracing::instrument(name = "Issue password reset token", skip(username, pool))]
+pub async fn issue_password_reset_token(
+    username: &str,
+    pool: &PgPool,
+) -> Result<Option<Secret<String>>, anyhow::Error> {
+    let user_id = sqlx::query_scalar!(
+        r#"SELECT user_id FROM users WHERE username = $1 AND status = 'active'"#,
+        username,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the user to reset the password of.")?;
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    let token = generate_opaque_token();
+    let now = Utc::now();
+    sqlx::query!(
+        r#"
+        INSERT INTO password_reset_tokens (token_hash, user_id, expires_at, created_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        hash_opaque_token(&token),
+        user_id,
+        now + Duration::seconds(DEFAULT_PASSWORD_RESET_TOKEN_TTL as i64),
+        now,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store the password reset token.")?;
+
+    Ok(Some(Secret::new(token)))
+}
+
+/// Use up a password reset token and return the user it was issued for.
+///
+/// A valid token is deleted along with the other pending tokens of the user
+/// once the transaction commits. An expired token is rejected, the error
+/// rolls the transaction back so the row stays until it is replaced.
+#[tracing::instrument(name = "Consume password reset token", skip(transaction, token))]
+pub async fn consume_password_reset_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    token: &Secret<String>,
+) -> Result<Uuid, PasswordResetError> {
+    let stored_token = sqlx::query!(
+        r#"
+        DELETE FROM password_reset_tokens
+        WHERE token_hash = $1
+        RETURNING user_id, expires_at
+        "#,
+        hash_opaque_token(token.expose_secret()),
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to retrieve the password reset token.")?
+    .ok_or_else(|| {
+        PasswordResetError::InvalidToken(anyhow::anyhow!("Unknown password reset token."))
+    })?;
+    if stored_token.expires_at < Utc::now() {
+        return Err(PasswordResetError::InvalidToken(anyhow::anyhow!(
+            "The password reset token is expired."
+        )));
+    }
+    sqlx::query!(
+        r#"DELETE FROM password_reset_tokens WHERE user_id = $1"#,
+        stored_token.user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to delete the password reset tokens of the user.")?;
+
+    Ok(stored_token.user_id)
+}
@@ -0,0 +1,208 @@
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::{generate_opaque_token, hash_opaque_token, Scope, DEFAULT_REFRESH_TOKEN_TTL};
+
+/// The session a refresh token was rotated in
+pub struct RotatedSession {
+    pub user_id: Uuid,
+    // `None` when the session was granted every scope of the role
+    pub requested_scopes: Option<Vec<Scope>>,
+    pub refresh_token: Secret<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RefreshTokenError {
+    #[error("Invalid refresh token.")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// Issue a new refresh token for the user.
+///
+/// A token issued at login starts a new family, rotated tokens keep the
+/// family and the requested scopes of the token they replace.
+#[tracing::instrument(name = "Issue refresh token", skip(transaction))]
+pub async fn issue_refresh_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    family_id: Option<Uuid>,
+    requested_scopes: Option<&[Scope]>,
+) -> Result<Secret<String>, anyhow::Error> {
+    let token = generate_opaque_token();
+    let now = Utc::now();
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (
+            id, user_id, family_id, token_hash, expires_at, created_at, scope
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        family_id.unwrap_or_else(Uuid::new_v4),
+        hash_opaque_token(&token),
+        now + Duration::seconds(DEFAULT_REFRESH_TOKEN_TTL as i64),
+        now,
+        requested_scopes.map(Scope::join),
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to store the refresh token.")?;
+
+    Ok(Secret::new(token))
+}
+
+/// Exchange a refresh token for a new one of the same family.
+///
+/// The presented token is revoked. Presenting a token that is already revoked
+/// means it leaked, so the whole family is revoked along with it.
+#[tracing::instrument(name = "Rotate refresh token", skip(token, pool))]
+pub async fn rotate_refresh_token(
+    token: &Secret<String>,
+    pool: &PgPool,
+) -> Result<RotatedSession, RefreshTokenError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let stored_token = sqlx::query!(
+        r#"
+        SELECT id, user_id, family_id, expires_at, revoked_at, scope
+        FROM refresh_tokens
+        WHERE token_hash = $1
+        FOR UPDATE
+        "#,
+        hash_opaque_token(token.expose_secret()),
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the refresh token.")?
+    .ok_or_else(|| RefreshTokenError::InvalidToken(anyhow::anyhow!("Unknown refresh token.")))?;
+
+    if stored_token.revoked_at.is_some() {
+        revoke_family(&mut transaction, stored_token.family_id).await?;
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit SQL transaction to revoke a token family.")?;
+        tracing::warn!(
+            family_id = %stored_token.family_id,
+            "A revoked refresh token was reused, the token family is revoked"
+        );
+        return Err(RefreshTokenError::InvalidToken(anyhow::anyhow!(
+            "The refresh token was already used."
+        )));
+    }
+    if stored_token.expires_at < Utc::now() {
+        return Err(RefreshTokenError::InvalidToken(anyhow::anyhow!(
+            "The refresh token is expired."
+        )));
+    }
+
+    let query = sqlx::query!(
+        r#"UPDATE refresh_tokens SET revoked_at = now() WHERE id = $1"#,
+        stored_token.id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to revoke the refresh token.")?;
+    let requested_scopes = stored_token
+        .scope
+        .as_deref()
+        .map(Scope::parse_list)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let refresh_token = issue_refresh_token(
+        &mut transaction,
+        stored_token.user_id,
+        Some(stored_token.family_id),
+        requested_scopes.as_deref(),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to rotate a refresh token.")?;
+
+    Ok(RotatedSession {
+        user_id: stored_token.user_id,
+        requested_scopes,
+        refresh_token,
+    })
+}
+
+/// Revoke the family of the given refresh token, ending the session it belongs to.
+#[tracing::instrument(name = "Revoke refresh token", skip(token, pool))]
+pub async fn revoke_refresh_token(
+    token: &Secret<String>,
+    pool: &PgPool,
+) -> Result<(), RefreshTokenError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let family_id = sqlx::query_scalar!(
+        r#"SELECT family_id FROM refresh_tokens WHERE token_hash = $1"#,
+        hash_opaque_token(token.expose_secret()),
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the refresh token.")?
+    .ok_or_else(|| RefreshTokenError::InvalidToken(anyhow::anyhow!("Unknown refresh token.")))?;
+
+    revoke_family(&mut transaction, family_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to revoke a token family.")?;
+
+    Ok(())
+}
+
+/// Revoke every refresh token of the user, ending all of their sessions.
+#[tracing::instrument(name = "Revoke refresh tokens of user", skip(transaction))]
+pub async fn revoke_user_refresh_tokens(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to revoke the refresh tokens of the user.")?;
+
+    Ok(())
+}
+
+async fn revoke_family(
+    transaction: &mut Transaction<'_, Postgres>,
+    family_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let query = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = now()
+        WHERE family_id = $1 AND revoked_at IS NULL
+        "#,
+        family_id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to revoke the token family.")?;
+
+    Ok(())
+}
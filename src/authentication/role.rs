@@ -0,0 +1,141 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{AuthenticatedUser, Scope};
+
+// Platform admins manage every host, host managers only the hosts they are
+// assigned to and guests cannot access the admin area at all
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    HostManager,
+    #[default]
+    Guest,
+}
+
+impl Role {
+    /// Scopes a user of the role may be granted
+    pub fn scopes(&self) -> Vec<Scope> {
+        match self {
+            Role::Admin => vec![
+                Scope::RoomsRead,
+                Scope::RoomsWrite,
+                Scope::BookingsWrite,
+                Scope::UsersAdmin,
+            ],
+            Role::HostManager => vec![Scope::RoomsRead, Scope::RoomsWrite, Scope::BookingsWrite],
+            // Guests book on the public routes, which take no token
+            Role::Guest => vec![],
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "host_manager" => Ok(Role::HostManager),
+            "guest" => Ok(Role::Guest),
+            _ => Err(format!("{} is not a valid role!", s)),
+        }
+    }
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        match self {
+            Role::Admin => "admin",
+            Role::HostManager => "host_manager",
+            Role::Guest => "guest",
+        }
+    }
+}
+
+/// Load the role of the user and the hosts they are assigned to
+#[tracing::instrument(name = "Get user access", skip(pool))]
+pub async fn get_user_access(
+    user_id: Uuid,
+    pool: &PgPool,
+) -> Result<AuthenticatedUser, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT u.role,
+            COALESCE(
+                array_agg(uh.host_id) FILTER (WHERE uh.host_id IS NOT NULL),
+                '{}'
+            ) AS "host_ids!"
+        FROM users u
+        LEFT JOIN user_hosts uh ON uh.user_id = u.user_id
+        WHERE u.user_id = $1
+        GROUP BY u.role
+        "#,
+        user_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the role of the user.")?
+    .ok_or_else(|| anyhow::anyhow!("User {} does not exist.", user_id))?;
+    let role = Role::parse(&row.role).map_err(|e| anyhow::anyhow!(e))?;
+
+    Ok(AuthenticatedUser {
+        user_id,
+        role,
+        host_ids: row.host_ids,
+        scopes: role.scopes(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_what_it_stores() {
+        for role in [Role::Admin, Role::HostManager, Role::Guest] {
+            assert_eq!(Role::parse(role.as_ref()), Ok(role));
+        }
+    }
+
+    #[test]
+    fn should_err_with_unknown_role() {
+        assert!(Role::parse("owner").is_err());
+    }
+
+    #[test]
+    fn should_grant_hosts_by_role() {
+        let host_id = Uuid::new_v4();
+        let test_cases = vec![
+            (Role::Admin, vec![], true, "admin manages every host"),
+            (
+                Role::HostManager,
+                vec![host_id],
+                true,
+                "host manager manages an assigned host",
+            ),
+            (
+                Role::HostManager,
+                vec![Uuid::new_v4()],
+                false,
+                "host manager does not manage another host",
+            ),
+            (
+                Role::Guest,
+                vec![host_id],
+                false,
+                "guest never manages a host",
+            ),
+        ];
+
+        for (role, host_ids, expected, error_message) in test_cases {
+            let user = AuthenticatedUser {
+                user_id: Uuid::new_v4(),
+                role,
+                host_ids,
+                scopes: role.scopes(),
+            };
+
+            assert_eq!(user.can_manage_host(host_id), expected, "{}", error_message);
+        }
+    }
+}
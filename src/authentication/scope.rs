@@ -0,0 +1,166 @@
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    HttpMessage,
+};
+use anyhow::anyhow;
+
+use super::{forbidden, AuthenticatedUser};
+
+pub const SCOPE_DELIMETER: char = ' ';
+
+// What an access token may be used for, see [RFC 6749 3.3]
+//
+// [RFC 6749 3.3]: https://www.rfc-editor.org/rfc/rfc6749#section-3.3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    RoomsRead,
+    RoomsWrite,
+    BookingsWrite,
+    UsersAdmin,
+}
+
+impl Scope {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "rooms:read" => Ok(Scope::RoomsRead),
+            "rooms:write" => Ok(Scope::RoomsWrite),
+            "bookings:write" => Ok(Scope::BookingsWrite),
+            "users:admin" => Ok(Scope::UsersAdmin),
+            _ => Err(format!("{} is not a valid scope!", s)),
+        }
+    }
+
+    /// Parse a space-delimited list of scopes
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(SCOPE_DELIMETER)
+            .filter(|scope| !scope.is_empty())
+            .map(Scope::parse)
+            .collect()
+    }
+
+    pub fn join(scopes: &[Scope]) -> String {
+        scopes
+            .iter()
+            .map(|scope| scope.as_ref())
+            .collect::<Vec<&str>>()
+            .join(&SCOPE_DELIMETER.to_string())
+    }
+}
+
+impl AsRef<str> for Scope {
+    fn as_ref(&self) -> &str {
+        match self {
+            Scope::RoomsRead => "rooms:read",
+            Scope::RoomsWrite => "rooms:write",
+            Scope::BookingsWrite => "bookings:write",
+            Scope::UsersAdmin => "users:admin",
+        }
+    }
+}
+
+/// Reject requests whose access token was not granted the scope.
+///
+/// It reads the [`AuthenticatedUser`] so the route must be behind
+/// [`super::reject_anonymous_users`], e.g.
+/// `#[get("/hosts", wrap = "RequireScope::new(Scope::RoomsRead)")]`
+pub struct RequireScope {
+    scope: Scope,
+}
+
+impl RequireScope {
+    pub fn new(scope: Scope) -> Self {
+        Self { scope }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScope
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequireScopeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireScopeMiddleware {
+            service,
+            scope: self.scope,
+        }))
+    }
+}
+
+pub struct RequireScopeMiddleware<S> {
+    service: S,
+    scope: Scope,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let granted = req
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .map(|user| user.scopes.contains(&self.scope))
+            .unwrap_or(false);
+        if !granted {
+            let e = anyhow!("The access token is not granted {}", self.scope.as_ref());
+            return Box::pin(ready(Err(forbidden(e))));
+        }
+
+        Box::pin(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_what_it_joins() {
+        let scopes = vec![
+            Scope::RoomsRead,
+            Scope::RoomsWrite,
+            Scope::BookingsWrite,
+            Scope::UsersAdmin,
+        ];
+
+        assert_eq!(Scope::parse_list(&Scope::join(&scopes)), Ok(scopes));
+    }
+
+    #[test]
+    fn should_err_with_invalid_scope_list() {
+        let test_cases = vec![
+            ("rooms:delete", "unknown scope"),
+            ("rooms:read,rooms:write", "comma separated"),
+            ("ROOMS:READ", "upper case"),
+        ];
+
+        for (invalid_scope, error_message) in test_cases {
+            assert!(
+                Scope::parse_list(invalid_scope).is_err(),
+                "{}",
+                error_message
+            );
+        }
+    }
+}
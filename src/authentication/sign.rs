@@ -1,48 +1,38 @@
-use base64::{
-    engine::{general_purpose::URL_SAFE_NO_PAD, GeneralPurpose},
-    Engine as _,
-};
-use ring::{rand::SystemRandom, rsa::KeyPair, signature};
-
 use super::{
     domain::{Header, Payload},
     error::MyError,
+    Algorithm,
+};
+use base64::{
+    engine::{general_purpose::URL_SAFE_NO_PAD, GeneralPurpose},
+    Engine as _,
 };
 
 static ENCODER: GeneralPurpose = URL_SAFE_NO_PAD;
 
-pub fn sign(payload: &Payload, secret: &[u8]) -> String {
-    let header = serde_json::to_string(&Header::new()).unwrap();
-    let encoded_header = ENCODER.encode(header);
+pub fn sign(payload: &Payload, secret: &[u8]) -> Result<String, MyError> {
+    sign_with_header(&Header::new(), payload, secret)
+}
+
+pub fn sign_with_header(
+    header: &Header,
+    payload: &Payload,
+    secret: &[u8],
+) -> Result<String, MyError> {
+    let header_json = serde_json::to_string(header).map_err(MyError::Serialization)?;
+    let encoded_header = ENCODER.encode(header_json);
 
-    let payload = serde_json::to_string(payload).unwrap();
+    let payload = serde_json::to_string(payload).map_err(MyError::Serialization)?;
     let encoded_payload = ENCODER.encode(payload);
 
-    let signature = create_signature(secret, &encoded_header, &encoded_payload).unwrap();
+    let alg = Algorithm::parse(&header.alg).map_err(|_| MyError::UnsupportedAlgorithm)?;
+    let message = format!("{encoded_header}.{encoded_payload}");
+    let signature = alg.sign(secret, message.as_bytes())?;
     let encoded_signature = ENCODER.encode(signature);
 
-    format!("{encoded_header}.{encoded_payload}.{encoded_signature}")
-}
-
-fn create_signature(
-    secret: &[u8],
-    encoded_header: &str,
-    encoded_payload: &str,
-) -> Result<Vec<u8>, MyError> {
-    let key_pair = KeyPair::from_pkcs8(secret).map_err(|_| MyError::BadPrivateKey)?;
-    let message = format!("{encoded_header}.{encoded_payload}");
-    let rng = SystemRandom::new();
-    let mut signature = vec![0; key_pair.public().modulus_len()];
-    key_pair
-        .sign(
-            &signature::RSA_PKCS1_SHA256,
-            &rng,
-            message.as_bytes(),
-            &mut signature,
-        )
-        .map_err(|_| MyError::OOM)?;
-
-    Ok(signature)
+    Ok(format!(
+        "{encoded_header}.{encoded_payload}.{encoded_signature}"
+    ))
 }
 
 pub fn get_private_key_pk8(path: &str) -> Result<Vec<u8>, MyError> {
@@ -52,13 +42,19 @@ pub fn get_private_key_pk8(path: &str) -> Result<Vec<u8>, MyError> {
     Ok(private_key_pk8)
 }
 
+pub fn get_public_key_der(path: &str) -> Result<Vec<u8>, MyError> {
+    let public_key_path = std::path::Path::new(path);
+    let public_key_der = read_file(public_key_path)?;
+
+    Ok(public_key_der)
+}
+
 fn read_file(path: &std::path::Path) -> Result<Vec<u8>, MyError> {
     use std::io::Read;
 
-    let mut file = std::fs::File::open(path).map_err(|e| MyError::IO(e))?;
+    let mut file = std::fs::File::open(path).map_err(MyError::IO)?;
     let mut contents: Vec<u8> = Vec::new();
-    file.read_to_end(&mut contents)
-        .map_err(|e| MyError::IO(e))?;
+    file.read_to_end(&mut contents).map_err(MyError::IO)?;
     Ok(contents)
 }
 
@@ -74,8 +70,8 @@ mod test {
         let payload_two = Payload::new("Tom".into());
         let secret =
             get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
-        let jwt_one = sign(&payload_one, &secret);
-        let jwt_two = sign(&payload_two, &secret);
+        let jwt_one = sign(&payload_one, &secret).expect("Failed to sign the token");
+        let jwt_two = sign(&payload_two, &secret).expect("Failed to sign the token");
 
         let sign_one = jwt_one.split(TOKEN_DELIMETER).collect::<Vec<&str>>()[2];
         let sign_two = jwt_two.split(TOKEN_DELIMETER).collect::<Vec<&str>>()[2];
@@ -91,8 +87,8 @@ mod test {
         let payload_two = Payload::new("Tom".into());
         let secret =
             get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
-        let jwt_one = sign(&payload_one, &secret);
-        let jwt_two = sign(&payload_two, &secret);
+        let jwt_one = sign(&payload_one, &secret).expect("Failed to sign the token");
+        let jwt_two = sign(&payload_two, &secret).expect("Failed to sign the token");
 
         let sign_one = jwt_one.split(TOKEN_DELIMETER).collect::<Vec<&str>>()[2];
         let sign_two = jwt_two.split(TOKEN_DELIMETER).collect::<Vec<&str>>()[2];
@@ -100,12 +96,22 @@ mod test {
         assert_ne!(sign_one, sign_two);
     }
 
+    #[test]
+    fn should_err_with_a_bad_private_key() {
+        let payload = Payload::new("Tom".into());
+
+        assert!(matches!(
+            sign(&payload, b"not-a-private-key"),
+            Err(MyError::BadPrivateKey)
+        ));
+    }
+
     #[test]
     fn should_add_expiry_to_the_payload() {
         let secret =
             get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
         let payload_one = Payload::new("Tom".into());
-        let jwt_one = sign(&payload_one, &secret);
+        let jwt_one = sign(&payload_one, &secret).expect("Failed to sign the token");
 
         let payload_one = jwt_one.split(TOKEN_DELIMETER).collect::<Vec<&str>>()[1];
         // Decode the payload and get "exp" key and its value is integer
@@ -0,0 +1,549 @@
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use data_encoding::BASE32_NOPAD;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::RngCore;
+use ring::hmac;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::{generate_opaque_token, hash_opaque_token, Scope, DEFAULT_MFA_CHALLENGE_TTL};
+
+const TOTP_ISSUER: &str = "Rush Booking";
+const TOTP_SECRET_LENGTH: usize = 20;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_PERIOD: u64 = 30;
+// Codes of the previous and the next period are accepted for clock drift
+const TOTP_SKEW: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+// A challenge is dropped after this many wrong codes
+const MAX_MFA_FAILED_ATTEMPTS: i32 = 5;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TotpError {
+    #[error("Two-factor authentication is already enabled.")]
+    AlreadyEnabled,
+    #[error("Invalid two-factor authentication code.")]
+    InvalidCode(#[source] anyhow::Error),
+    // A wrong code for a challenge of the user, counted as a failed login
+    #[error("Invalid two-factor authentication code.")]
+    WrongCode(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// What a user needs to add the account to an authenticator app
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// Returned by `/login` instead of the tokens when the user has TOTP enabled
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct MfaChallenge {
+    pub challenge_token: String,
+    pub expires_in: u64,
+}
+
+/// A random base32 encoded TOTP secret
+pub fn generate_totp_secret() -> String {
+    let mut bytes = [0u8; TOTP_SECRET_LENGTH];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// Key URI understood by authenticator apps, usually shown as a QR code
+///
+/// # Examples
+///
+/// ```
+/// use rush_booking::authentication::otpauth_uri;
+///
+/// assert_eq!(
+///     otpauth_uri("admin@tdog.com", "GEZDGNBVGY3TQOJQ"),
+///     "otpauth://totp/Rush%20Booking:admin%40tdog%2Ecom?secret=GEZDGNBVGY3TQOJQ\
+///     &issuer=Rush%20Booking&algorithm=SHA1&digits=6&period=30"
+/// );
+/// ```
+pub fn otpauth_uri(account: &str, secret: &str) -> String {
+    let issuer = utf8_percent_encode(TOTP_ISSUER, NON_ALPHANUMERIC);
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        utf8_percent_encode(account, NON_ALPHANUMERIC),
+        secret,
+        issuer,
+        TOTP_DIGITS,
+        TOTP_PERIOD
+    )
+}
+
+/// The TOTP code of the base32 encoded secret at the given unix time, see [RFC 6238]
+///
+/// [RFC 6238]: https://www.rfc-editor.org/rfc/rfc6238
+pub fn totp_code(secret: &str, unix_time: u64) -> Result<String, String> {
+    let key = decode_totp_secret(secret)?;
+    Ok(hotp(&key, unix_time / TOTP_PERIOD))
+}
+
+/// Check a TOTP code and return the time step it belongs to
+pub fn verify_totp_code(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let key = decode_totp_secret(secret).ok()?;
+    let step = unix_time / TOTP_PERIOD;
+    (step.saturating_sub(TOTP_SKEW)..=step + TOTP_SKEW).find(|&step| hotp(&key, step) == code)
+}
+
+fn decode_totp_secret(secret: &str) -> Result<Vec<u8>, String> {
+    BASE32_NOPAD
+        .decode(secret.trim_end_matches('=').as_bytes())
+        .map_err(|_| format!("{} is not a base32 encoded secret.", secret))
+}
+
+// HOTP with HMAC-SHA1 and dynamic truncation, see RFC 4226 5.3
+fn hotp(key: &[u8], counter: u64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+    let digest = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = digest.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+/// Recovery codes are case-insensitive and may be typed with or without dashes
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; RECOVERY_CODE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = BASE32_NOPAD.encode(&bytes)[..RECOVERY_CODE_LENGTH].to_lowercase();
+    format!(
+        "{}-{}",
+        &code[..RECOVERY_CODE_LENGTH / 2],
+        &code[RECOVERY_CODE_LENGTH / 2..]
+    )
+}
+
+/// Whether the user confirmed a TOTP enrollment
+#[tracing::instrument(name = "Check TOTP enrollment", skip(pool))]
+pub async fn is_totp_enabled(user_id: Uuid, pool: &PgPool) -> Result<bool, anyhow::Error> {
+    let enabled = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM user_totp WHERE user_id = $1 AND confirmed_at IS NOT NULL
+        ) AS "exists!"
+        "#,
+        user_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to check the TOTP enrollment of the user.")?;
+
+    Ok(enabled)
+}
+
+/// Generate a new TOTP secret for the user, it is only used once confirmed.
+///
+/// A pending enrollment is replaced, an enabled one is left untouched.
+#[tracing::instrument(name = "Start TOTP enrollment", skip(pool))]
+pub async fn start_totp_enrollment(
+    user_id: Uuid,
+    pool: &PgPool,
+) -> Result<Secret<String>, TotpError> {
+    let secret = generate_totp_secret();
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO user_totp (user_id, secret, created_at)
+        VALUES ($1, $2, now())
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret, created_at = EXCLUDED.created_at
+        WHERE user_totp.confirmed_at IS NULL
+        "#,
+        user_id,
+        secret,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store the TOTP secret.")?;
+    if result.rows_affected() == 0 {
+        return Err(TotpError::AlreadyEnabled);
+    }
+
+    Ok(Secret::new(secret))
+}
+
+/// Enable TOTP with a first code from the authenticator app and return the
+/// recovery codes, they are only stored as their SHA-256 hash.
+#[tracing::instrument(name = "Confirm TOTP enrollment", skip(code, pool))]
+pub async fn confirm_totp_enrollment(
+    user_id: Uuid,
+    code: &str,
+    pool: &PgPool,
+) -> Result<Vec<Secret<String>>, TotpError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let enrollment = sqlx::query!(
+        r#"SELECT secret, confirmed_at FROM user_totp WHERE user_id = $1 FOR UPDATE"#,
+        user_id,
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the TOTP enrollment.")?
+    .ok_or_else(|| TotpError::InvalidCode(anyhow::anyhow!("No pending TOTP enrollment.")))?;
+    if enrollment.confirmed_at.is_some() {
+        return Err(TotpError::AlreadyEnabled);
+    }
+    let step = verify_totp_code(&enrollment.secret, code, unix_now())
+        .ok_or_else(|| TotpError::InvalidCode(anyhow::anyhow!("Wrong TOTP code.")))?;
+    sqlx::query!(
+        r#"
+        UPDATE user_totp
+        SET confirmed_at = now(), last_used_step = $1
+        WHERE user_id = $2
+        "#,
+        step as i64,
+        user_id,
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to enable TOTP.")?;
+    let recovery_codes = replace_recovery_codes(&mut transaction, user_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to enable TOTP.")?;
+
+    Ok(recovery_codes)
+}
+
+async fn replace_recovery_codes(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Vec<Secret<String>>, anyhow::Error> {
+    sqlx::query!(
+        r#"DELETE FROM totp_recovery_codes WHERE user_id = $1"#,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to delete the recovery codes.")?;
+    let recovery_codes = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect::<Vec<_>>();
+    let code_hashes = recovery_codes
+        .iter()
+        .map(|code| hash_opaque_token(&normalize_recovery_code(code)))
+        .collect::<Vec<_>>();
+    sqlx::query!(
+        r#"
+        INSERT INTO totp_recovery_codes (code_hash, user_id, created_at)
+        SELECT code_hash, $2, now() FROM UNNEST($1::TEXT[]) AS code_hash
+        "#,
+        &code_hashes,
+        user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to store the recovery codes.")?;
+
+    Ok(recovery_codes.into_iter().map(Secret::new).collect())
+}
+
+/// Issue the short-lived token a user with TOTP enabled exchanges, along with
+/// a code, for the real tokens
+#[tracing::instrument(name = "Issue MFA challenge", skip(pool))]
+pub async fn issue_mfa_challenge(
+    user_id: Uuid,
+    requested_scopes: Option<&[Scope]>,
+    pool: &PgPool,
+) -> Result<MfaChallenge, anyhow::Error> {
+    let token = generate_opaque_token();
+    let now = Utc::now();
+    sqlx::query!(
+        r#"
+        INSERT INTO mfa_challenges (token_hash, user_id, scope, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        hash_opaque_token(&token),
+        user_id,
+        requested_scopes.map(Scope::join),
+        now + Duration::seconds(DEFAULT_MFA_CHALLENGE_TTL as i64),
+        now,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store the MFA challenge.")?;
+
+    Ok(MfaChallenge {
+        challenge_token: token,
+        expires_in: DEFAULT_MFA_CHALLENGE_TTL,
+    })
+}
+
+/// The user an unexpired challenge was issued for, `None` for an unknown one
+#[tracing::instrument(name = "Get MFA challenge user", skip(token, pool))]
+pub async fn get_mfa_challenge_user(
+    token: &Secret<String>,
+    pool: &PgPool,
+) -> Result<Option<Uuid>, anyhow::Error> {
+    let user_id = sqlx::query_scalar!(
+        r#"
+        SELECT user_id
+        FROM mfa_challenges
+        WHERE token_hash = $1 AND expires_at > now()
+        "#,
+        hash_opaque_token(token.expose_secret()),
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the MFA challenge.")?;
+
+    Ok(user_id)
+}
+
+/// Check the TOTP code, or a recovery code, for a challenge and return the
+/// user along with the scopes requested at login.
+///
+/// The challenge is used up by a valid code, or after too many wrong ones.
+/// A TOTP code is only accepted once.
+#[tracing::instrument(name = "Verify MFA challenge", skip(token, code, pool))]
+pub async fn verify_mfa_challenge(
+    token: &Secret<String>,
+    code: &str,
+    pool: &PgPool,
+) -> Result<(Uuid, Option<Vec<Scope>>), TotpError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let token_hash = hash_opaque_token(token.expose_secret());
+    let challenge = sqlx::query!(
+        r#"
+        SELECT user_id, scope, failed_attempts, expires_at
+        FROM mfa_challenges
+        WHERE token_hash = $1
+        FOR UPDATE
+        "#,
+        token_hash,
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the MFA challenge.")?
+    .filter(|challenge| challenge.expires_at > Utc::now())
+    .ok_or_else(|| TotpError::InvalidCode(anyhow::anyhow!("Unknown or expired MFA challenge.")))?;
+
+    if !check_second_factor(&mut transaction, challenge.user_id, code).await? {
+        let query = if challenge.failed_attempts + 1 >= MAX_MFA_FAILED_ATTEMPTS {
+            sqlx::query!(
+                r#"DELETE FROM mfa_challenges WHERE token_hash = $1"#,
+                token_hash
+            )
+        } else {
+            sqlx::query!(
+                r#"
+                UPDATE mfa_challenges
+                SET failed_attempts = failed_attempts + 1
+                WHERE token_hash = $1
+                "#,
+                token_hash
+            )
+        };
+        query
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to record a wrong MFA code.")?;
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit SQL transaction to record a wrong MFA code.")?;
+        return Err(TotpError::WrongCode(challenge.user_id));
+    }
+
+    sqlx::query!(
+        r#"DELETE FROM mfa_challenges WHERE token_hash = $1"#,
+        token_hash
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to use up the MFA challenge.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to use up an MFA challenge.")?;
+    let requested_scopes = challenge
+        .scope
+        .as_deref()
+        .map(Scope::parse_list)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    Ok((challenge.user_id, requested_scopes))
+}
+
+/// Check a TOTP code, or a recovery code, of a logged in user before a change
+/// to the account. The code is used up like at login.
+#[tracing::instrument(name = "Verify second factor", skip(code, pool))]
+pub async fn verify_second_factor(
+    user_id: Uuid,
+    code: &str,
+    pool: &PgPool,
+) -> Result<bool, anyhow::Error> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let verified = check_second_factor(&mut transaction, user_id, code).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to use up a second factor.")?;
+
+    Ok(verified)
+}
+
+// Accept a TOTP code of a later time step than the last one used, or an
+// unused recovery code
+async fn check_second_factor(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    code: &str,
+) -> Result<bool, anyhow::Error> {
+    let totp = sqlx::query!(
+        r#"
+        SELECT secret, last_used_step
+        FROM user_totp
+        WHERE user_id = $1 AND confirmed_at IS NOT NULL
+        FOR UPDATE
+        "#,
+        user_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to retrieve the TOTP secret.")?
+    .ok_or_else(|| anyhow::anyhow!("TOTP is not enabled for user {}.", user_id))?;
+    let step = verify_totp_code(&totp.secret, code.trim(), unix_now())
+        .filter(|&step| totp.last_used_step.is_none_or(|last| step as i64 > last));
+    if let Some(step) = step {
+        sqlx::query!(
+            r#"UPDATE user_totp SET last_used_step = $1 WHERE user_id = $2"#,
+            step as i64,
+            user_id,
+        )
+        .execute(&mut **transaction)
+        .await
+        .context("Failed to record the used TOTP code.")?;
+        return Ok(true);
+    }
+
+    let used_recovery_code = sqlx::query!(
+        r#"
+        UPDATE totp_recovery_codes
+        SET used_at = now()
+        WHERE code_hash = $1 AND user_id = $2 AND used_at IS NULL
+        "#,
+        hash_opaque_token(&normalize_recovery_code(code)),
+        user_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to use up the recovery code.")?
+    .rows_affected()
+        > 0;
+    if used_recovery_code {
+        tracing::warn!(%user_id, "A recovery code was used to log in");
+    }
+
+    Ok(used_recovery_code)
+}
+
+fn unix_now() -> u64 {
+    Utc::now().timestamp() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The SHA1 seed of RFC 6238 Appendix B, "12345678901234567890"
+    const RFC_6238_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn should_compute_the_rfc_6238_test_vectors() {
+        // The RFC lists 8 digit codes, these are their last 6 digits
+        let test_cases = vec![
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+
+        for (unix_time, code) in test_cases {
+            assert_eq!(totp_code(RFC_6238_SECRET, unix_time).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn should_accept_codes_of_adjacent_time_steps_only() {
+        let secret = generate_totp_secret();
+        let now = 1_700_000_000;
+        let test_cases = vec![
+            (now - TOTP_PERIOD, true, "the previous step"),
+            (now, true, "the current step"),
+            (now + TOTP_PERIOD, true, "the next step"),
+            (now - 2 * TOTP_PERIOD, false, "an older step"),
+            (now + 2 * TOTP_PERIOD, false, "a later step"),
+        ];
+
+        for (code_time, accepted, error_message) in test_cases {
+            let code = totp_code(&secret, code_time).unwrap();
+
+            assert_eq!(
+                verify_totp_code(&secret, &code, now).is_some(),
+                accepted,
+                "Unexpected verification of a code of {}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_malformed_codes_and_secrets() {
+        let secret = generate_totp_secret();
+
+        assert!(verify_totp_code(&secret, "", 59).is_none());
+        assert!(verify_totp_code(&secret, "abcdef", 59).is_none());
+        assert!(verify_totp_code("not base32!", "287082", 59).is_none());
+    }
+
+    #[test]
+    fn should_normalize_recovery_codes_as_generated() {
+        let code = generate_recovery_code();
+
+        assert_eq!(code.len(), RECOVERY_CODE_LENGTH + 1);
+        assert_eq!(
+            normalize_recovery_code(&code.to_uppercase().replace('-', " ")),
+            normalize_recovery_code(&code)
+        );
+    }
+}
@@ -1,18 +1,73 @@
-use super::domain::{Token, TokenError};
+use super::{
+    domain::{Token, TokenError, DEFAULT_TOKEN_TYPE},
+    Algorithm, JwtKeys,
+};
 
-/// Verify the given token
+/// Verify the given token against our keys and claims
+///
+/// The header must name the algorithm we sign with, the signature must match
+/// `header.payload` under the key named by `kid` and the token must be issued
+/// by us, for us, and not expired.
 ///
 /// # Examples
 ///
 /// ```
-/// use rush_booking::authentication::{Payload, Header, Token, TokenError, sign, verify};
-/// let header = Header::new();
-/// let payload = Payload::new("Tom".into());
-/// let token = Token::new(header, payload);
+/// use rush_booking::authentication::{
+///     decode, get_private_key_pk8, get_public_key_der, sign, verify, Algorithm, JwtKeys,
+/// };
+/// let private_key = get_private_key_pk8("./private-key.pk8").unwrap();
+/// let public_key = get_public_key_der("./public-key.der").unwrap();
+/// let keys = JwtKeys::new(
+///     "primary",
+///     Algorithm::Rs256,
+///     private_key,
+///     public_key,
+///     "rush_booking",
+///     "rush_booking_api",
+/// );
+/// let token = sign(&keys.new_payload("Tom".into()), keys.private_key()).unwrap();
+/// let token = decode(&token).unwrap();
 ///
-/// assert!(verify(&token).is_ok());
+/// assert!(verify(&token, &keys).is_ok());
 /// ```
-pub fn verify(token: &Token) -> Result<(), TokenError> {
+pub fn verify(token: &Token, keys: &JwtKeys) -> Result<(), TokenError> {
+    let alg = Algorithm::parse(&token.header.alg).map_err(|_| TokenError::InvalidAlg)?;
+
+    if token.header.typ != DEFAULT_TOKEN_TYPE {
+        return Err(TokenError::InvalidTyp);
+    }
+
+    let public_keys = keys.verification_keys(token.header.kid.as_deref());
+    if public_keys.is_empty() {
+        return Err(TokenError::UnknownKey);
+    }
+    // A key only verifies the algorithm it is configured with
+    let public_keys = public_keys
+        .into_iter()
+        .filter(|(key_alg, _)| *key_alg == alg)
+        .collect::<Vec<_>>();
+    if public_keys.is_empty() {
+        return Err(TokenError::InvalidAlg);
+    }
+    let is_signed_by_us = public_keys.into_iter().any(|(_, public_key)| {
+        alg.verify(public_key, token.signing_input.as_bytes(), &token.signature)
+    });
+    if !is_signed_by_us {
+        return Err(TokenError::InvalidSignature);
+    }
+
+    if token.payload.sub.is_empty() {
+        return Err(TokenError::MissingRequiredClaims);
+    }
+
+    if token.payload.iss != keys.issuer() {
+        return Err(TokenError::InvalidIssuer);
+    }
+
+    if token.payload.aud != keys.audience() {
+        return Err(TokenError::InvalidAudience);
+    }
+
     if token.is_expired() {
         return Err(TokenError::Expired);
     }
@@ -24,35 +79,216 @@ pub fn verify(token: &Token) -> Result<(), TokenError> {
 mod test {
     use std::{thread, time::Duration};
 
-    use claims::assert_ok;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use claims::{assert_err, assert_ok};
 
-    use crate::authentication::{decode, domain::Payload, get_private_key_pk8, sign, verify};
+    use crate::authentication::{
+        decode,
+        domain::{Payload, TokenError},
+        get_private_key_pk8, get_public_key_der, sign, verify, Algorithm, AuthenticatedUser,
+        JwtKeys, Role,
+    };
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use uuid::Uuid;
+
+    fn keys_named(kid: &str) -> JwtKeys {
+        let private_key =
+            get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
+        let public_key =
+            get_public_key_der("./public-key.der").expect("Failed to retrieve the public key");
+
+        JwtKeys::new(
+            kid,
+            Algorithm::Rs256,
+            private_key,
+            public_key,
+            "rush_booking",
+            "rush_booking_api",
+        )
+    }
+
+    fn keys() -> JwtKeys {
+        keys_named("primary")
+    }
 
     #[test]
     fn should_err_with_the_expired_token() {
-        let secret =
-            get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
+        let keys = keys();
         // Define token has exp as time it created;
-        let payload = Payload::new("Tom".into()).set_exp(Duration::from_secs(0));
+        let payload = keys
+            .new_payload("Tom".into())
+            .set_exp(Duration::from_secs(0));
         // Into future 1s
         thread::sleep(Duration::from_secs(1));
-        let token = sign(&payload, &secret);
+        let token = sign(&payload, keys.private_key()).expect("Failed to sign the token");
 
         let decoded_token = decode(&token).unwrap();
 
-        assert!(verify(&decoded_token).is_err());
+        assert!(matches!(
+            verify(&decoded_token, &keys),
+            Err(TokenError::Expired)
+        ));
     }
 
     #[test]
     fn should_success_with_the_valid_token() {
-        let secret =
-            get_private_key_pk8("./private-key.pk8").expect("Failed to retrieve the private key");
+        let keys = keys();
         // Define token has exp in the future;
-        let payload = Payload::new("Tom".into()).set_exp(Duration::from_secs(10));
-        let token = sign(&payload, &secret);
+        let payload = keys
+            .new_payload("Tom".into())
+            .set_exp(Duration::from_secs(10));
+        let token = sign(&payload, keys.private_key()).expect("Failed to sign the token");
+
+        let decoded_token = decode(&token).unwrap();
+
+        assert_ok!(verify(&decoded_token, &keys));
+    }
+
+    #[test]
+    fn should_err_with_foreign_claims() {
+        let keys = keys();
+        let test_cases = vec![
+            (
+                Payload::new("Tom".into()).set_audience(keys.audience()),
+                "has no issuer",
+            ),
+            (
+                keys.new_payload("Tom".into()).set_issuer("someone_else"),
+                "is issued by someone else",
+            ),
+            (
+                keys.new_payload("Tom".into()).set_audience("another_api"),
+                "is meant for another audience",
+            ),
+            (keys.new_payload("".into()), "has no subject"),
+        ];
+
+        for (payload, error_message) in test_cases {
+            let token = sign(&payload, keys.private_key()).expect("Failed to sign the token");
+            let decoded_token = decode(&token).unwrap();
+
+            assert_err!(verify(&decoded_token, &keys), "{}", error_message);
+        }
+    }
+
+    #[test]
+    fn should_err_with_the_tampered_token() {
+        let keys = keys();
+        let token = sign(&keys.new_payload("Tom".into()), keys.private_key())
+            .expect("Failed to sign the token");
+        let segments = token.split('.').collect::<Vec<&str>>();
+        let forged_payload = serde_json::to_string(&keys.new_payload("Jerry".into())).unwrap();
+        let forged_header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#);
+        let test_cases = vec![
+            (
+                format!(
+                    "{}.{}.{}",
+                    segments[0],
+                    URL_SAFE_NO_PAD.encode(forged_payload),
+                    segments[2]
+                ),
+                "has a replaced payload",
+            ),
+            (
+                format!("{}.{}.{}", forged_header, segments[1], segments[2]),
+                "claims another algorithm",
+            ),
+            (
+                format!("{}.{}.", segments[0], segments[1]),
+                "has no signature",
+            ),
+        ];
+
+        for (tampered_token, error_message) in test_cases {
+            let decoded_token = decode(&tampered_token).unwrap();
+
+            assert_err!(verify(&decoded_token, &keys), "{}", error_message);
+        }
+    }
+
+    #[test]
+    fn should_verify_tokens_of_a_retired_key_after_rotation() {
+        let retired_keys = keys_named("retired");
+        let user = AuthenticatedUser {
+            user_id: Uuid::new_v4(),
+            role: Role::Guest,
+            host_ids: vec![],
+            scopes: vec![],
+        };
+        let token = retired_keys.access_token(&user).unwrap();
+        let decoded_token = decode(&token).unwrap();
+        assert_eq!(decoded_token.header.kid.as_deref(), Some("retired"));
+
+        let rotated_keys = keys_named("current").add_verification_key(
+            "retired",
+            Algorithm::Rs256,
+            get_public_key_der("./public-key.der").unwrap(),
+        );
+        assert_ok!(verify(&decoded_token, &rotated_keys));
+
+        let dropped_keys = keys_named("current");
+        assert!(matches!(
+            verify(&decoded_token, &dropped_keys),
+            Err(TokenError::UnknownKey)
+        ));
+    }
+
+    fn ed25519_keys(kid: &str) -> JwtKeys {
+        let private_key = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let public_key = Ed25519KeyPair::from_pkcs8(private_key.as_ref())
+            .unwrap()
+            .public_key()
+            .as_ref()
+            .to_vec();
+
+        JwtKeys::new(
+            kid,
+            Algorithm::EdDsa,
+            private_key.as_ref().to_vec(),
+            public_key,
+            "rush_booking",
+            "rush_booking_api",
+        )
+    }
+
+    #[test]
+    fn should_verify_tokens_signed_with_eddsa() {
+        let keys = ed25519_keys("edge");
+        let user = AuthenticatedUser {
+            user_id: Uuid::new_v4(),
+            role: Role::Guest,
+            host_ids: vec![],
+            scopes: vec![],
+        };
+        let token = keys.access_token(&user).unwrap();
+
+        let decoded_token = decode(&token).unwrap();
+
+        assert_eq!(decoded_token.header.alg, "EdDSA");
+        assert_ok!(verify(&decoded_token, &keys));
+    }
+
+    #[test]
+    fn should_err_when_the_alg_does_not_match_the_key() {
+        let keys = ed25519_keys("primary");
+        // Signed with RS256 under the kid of an EdDSA key
+        let token = keys_named("primary")
+            .access_token(&AuthenticatedUser {
+                user_id: Uuid::new_v4(),
+                role: Role::Guest,
+                host_ids: vec![],
+                scopes: vec![],
+            })
+            .unwrap();
 
         let decoded_token = decode(&token).unwrap();
 
-        assert_ok!(verify(&decoded_token));
+        assert!(matches!(
+            verify(&decoded_token, &keys),
+            Err(TokenError::InvalidAlg)
+        ));
     }
 }
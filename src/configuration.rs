@@ -1,3 +1,5 @@
+use crate::{authentication::Algorithm, domain::CustomerEmail, email_client::EmailClient};
+use argon2::Params;
 use secrecy::{ExposeSecret, Secret};
 use serde_aux::field_attributes::deserialize_number_from_string;
 use sqlx::{
@@ -9,6 +11,10 @@ use sqlx::{
 pub struct Settings {
     pub application: ApplicationSettings,
     pub database: DatabaseSettings,
+    pub jwt: JwtSettings,
+    pub email_client: EmailClientSettings,
+    pub login_throttle: LoginThrottleSettings,
+    pub password_hashing: PasswordHashingSettings,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -31,6 +37,88 @@ pub struct DatabaseSettings {
     pub require_ssl: bool,
 }
 
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtSettings {
+    pub issuer: String,
+    pub audience: String,
+    // The first key signs new tokens, the others only verify the tokens
+    // they signed before a rotation
+    pub keys: Vec<JwtKeySettings>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct JwtKeySettings {
+    pub kid: String,
+    // RS256 unless set, EdDSA keys come from `scripts/init_jwt_keypair.sh` as well
+    #[serde(default)]
+    pub alg: Algorithm,
+    // Generated by `scripts/init_jwt_keypair.sh`, retired keys do not need one
+    pub private_key_path: Option<String>,
+    pub public_key_path: String,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct EmailClientSettings {
+    pub base_url: String,
+    pub sender_email: String,
+    pub authorization_token: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_miliseconds: u64,
+}
+
+impl EmailClientSettings {
+    pub fn sender(&self) -> Result<CustomerEmail, String> {
+        CustomerEmail::parse(self.sender_email.clone())
+    }
+
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_miliseconds)
+    }
+
+    pub fn client(self) -> EmailClient {
+        let sender_email = self.sender().expect("Invalid sender email address.");
+        let timeout = self.timeout();
+        EmailClient::new(
+            self.base_url,
+            sender_email,
+            self.authorization_token,
+            timeout,
+        )
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct LoginThrottleSettings {
+    // Failed logins allowed before the account or the IP is locked out,
+    // every further failure doubles the lockout
+    pub account_free_attempts: u32,
+    pub ip_free_attempts: u32,
+    pub base_lockout_seconds: u64,
+    pub max_lockout_seconds: u64,
+    // Failures are forgotten after this long without a new one
+    pub reset_after_seconds: u64,
+    // Read the client IP from `Forwarded`/`X-Forwarded-For`, only behind a trusted proxy
+    pub trust_forwarded_for: bool,
+}
+
+/// Argon2id cost of new password hashes, raising it upgrades the stored
+/// hashes as users log in
+#[derive(serde::Deserialize, Clone)]
+pub struct PasswordHashingSettings {
+    // Memory size in KiB
+    pub m_cost: u32,
+    // Number of iterations
+    pub t_cost: u32,
+    // Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl PasswordHashingSettings {
+    pub fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+    }
+}
+
 impl DatabaseSettings {
     pub fn without_db(&self) -> PgConnectOptions {
         let ssl_mode = if self.require_ssl {
@@ -41,7 +129,7 @@ impl DatabaseSettings {
 
         let options = PgConnectOptions::new()
             .username(&self.username)
-            .password(&self.password.expose_secret())
+            .password(self.password.expose_secret())
             .host(&self.host)
             .port(self.port)
             .ssl_mode(ssl_mode);
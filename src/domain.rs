@@ -1,10 +1,15 @@
 mod booking;
+mod cancellation;
 mod customer;
+mod pagination;
+mod password;
 mod repository;
-mod sealed_trait;
-mod service;
+mod state;
 
 pub use booking::*;
+pub use cancellation::*;
 pub use customer::*;
+pub use pagination::*;
+pub use password::*;
 pub use repository::*;
-mod state;
+pub use state::*;
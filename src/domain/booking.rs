@@ -1,6 +1,9 @@
+use chrono::{NaiveDate, Utc};
 use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
+use super::{CancellationPolicy, CustomerEmail};
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GeneralName(String);
 
@@ -43,11 +46,24 @@ pub struct Host {
     pub name: GeneralName,
 }
 
+#[derive(serde::Serialize)]
+pub struct HostWithRooms {
+    #[serde(flatten)]
+    pub host: Host,
+    pub rooms: Vec<Room>,
+}
+
 pub struct NewHost {
     pub name: GeneralName,
     pub category: HostCategory,
 }
 
+// Fields left as `None` keep their current value
+pub struct HostChanges {
+    pub name: Option<GeneralName>,
+    pub category: Option<HostCategory>,
+}
+
 // Different kind of romm's container
 // It can be belong to a hotel or local house
 // But not both
@@ -93,6 +109,42 @@ pub struct Room {
     pub description: String,
     // Assumption that total beds is small
     pub number_of_beds: u16,
+    // In the smallest unit of the currency, e.g. cents
+    pub price_per_night: i64,
+    pub cancellation_policy: CancellationPolicy,
+}
+
+/// Highest price per night, in the smallest unit of the currency, so that
+/// the total of any stay fits in a `BIGINT`
+pub const MAX_PRICE_PER_NIGHT: i64 = 100_000_000;
+
+pub fn parse_price_per_night(price_per_night: i64) -> Result<i64, String> {
+    if price_per_night < 0 {
+        Err("The price per night cannot be negative".to_string())
+    } else if price_per_night > MAX_PRICE_PER_NIGHT {
+        Err(format!(
+            "The price per night cannot be more than {}",
+            MAX_PRICE_PER_NIGHT
+        ))
+    } else {
+        Ok(price_per_night)
+    }
+}
+
+/// Highest number of beds of a room, it is stored as a `SMALLINT`
+pub const MAX_NUMBER_OF_BEDS: u16 = i16::MAX as u16;
+
+pub fn parse_number_of_beds(number_of_beds: u16) -> Result<u16, String> {
+    if number_of_beds == 0 {
+        Err("A room needs at least one bed".to_string())
+    } else if number_of_beds > MAX_NUMBER_OF_BEDS {
+        Err(format!(
+            "A room cannot have more than {} beds",
+            MAX_NUMBER_OF_BEDS
+        ))
+    } else {
+        Ok(number_of_beds)
+    }
 }
 
 pub struct NewRoom {
@@ -101,13 +153,83 @@ pub struct NewRoom {
     pub description: String,
     // Assumption that total beds is small
     pub number_of_beds: u16,
+    pub price_per_night: i64,
+    pub cancellation_policy: CancellationPolicy,
+}
+
+// Fields left as `None` keep their current value
+pub struct RoomChanges {
+    pub name: Option<GeneralName>,
+    pub description: Option<String>,
+    pub number_of_beds: Option<u16>,
+    pub price_per_night: Option<i64>,
+    pub cancellation_policy: Option<CancellationPolicy>,
+}
+
+// Nights are counted from check-in to check-out,
+// so the check-out day itself is free for the next guest
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct StayRange {
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+}
+
+impl StayRange {
+    pub fn parse(check_in: NaiveDate, check_out: NaiveDate) -> Result<StayRange, String> {
+        let today = Utc::now().date_naive();
+
+        if check_in < today {
+            Err(format!("Check-in date {} is in the past", check_in))
+        } else if check_out <= check_in {
+            Err(format!(
+                "Check-out date {} must be after check-in date {}",
+                check_out, check_in
+            ))
+        } else {
+            Ok(Self {
+                check_in,
+                check_out,
+            })
+        }
+    }
+
+    pub fn check_in(&self) -> NaiveDate {
+        self.check_in
+    }
+
+    pub fn check_out(&self) -> NaiveDate {
+        self.check_out
+    }
+
+    pub fn nights(&self) -> i64 {
+        (self.check_out - self.check_in).num_days()
+    }
+}
+
+pub struct NewBooking {
+    pub room_id: Uuid,
+    pub guest_name: GeneralName,
+    pub guest_email: CustomerEmail,
+    pub stay: StayRange,
+}
+
+// Fields left as `None` keep their current value
+pub struct BookingChanges {
+    pub guest_email: CustomerEmail,
+    pub room_id: Option<Uuid>,
+    pub check_in: Option<NaiveDate>,
+    pub check_out: Option<NaiveDate>,
 }
 
 #[cfg(test)]
 mod tests {
-    use claims::assert_err;
+    use chrono::{Days, Utc};
+    use claims::{assert_err, assert_ok};
 
-    use super::HostCategory;
+    use super::{
+        parse_number_of_beds, parse_price_per_night, HostCategory, StayRange, MAX_NUMBER_OF_BEDS,
+        MAX_PRICE_PER_NIGHT,
+    };
 
     #[test]
     fn invalid_hotel_category_is_rejected() {
@@ -130,4 +252,59 @@ mod tests {
             HostCategory::GuestHouse
         ));
     }
+
+    #[test]
+    fn price_per_night_out_of_range_is_rejected() {
+        assert_err!(parse_price_per_night(-1));
+        assert_err!(parse_price_per_night(MAX_PRICE_PER_NIGHT + 1));
+        assert_err!(parse_price_per_night(i64::MAX));
+        assert_ok!(parse_price_per_night(0));
+        assert_ok!(parse_price_per_night(MAX_PRICE_PER_NIGHT));
+    }
+
+    #[test]
+    fn number_of_beds_out_of_range_is_rejected() {
+        assert_err!(parse_number_of_beds(0));
+        assert_err!(parse_number_of_beds(MAX_NUMBER_OF_BEDS + 1));
+        assert_err!(parse_number_of_beds(u16::MAX));
+        assert_ok!(parse_number_of_beds(1));
+        assert_ok!(parse_number_of_beds(MAX_NUMBER_OF_BEDS));
+    }
+
+    #[test]
+    fn invalid_stay_range_is_rejected() {
+        let today = Utc::now().date_naive();
+        let test_cases = vec![
+            (
+                today - Days::new(1),
+                today + Days::new(1),
+                "check-in is in the past",
+            ),
+            (today, today, "check-out is the same day as check-in"),
+            (
+                today + Days::new(3),
+                today + Days::new(1),
+                "check-out is before check-in",
+            ),
+        ];
+
+        for (check_in, check_out, error_message) in test_cases {
+            assert!(
+                StayRange::parse(check_in, check_out).is_err(),
+                "The stay range was accepted when {}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn valid_stay_range_is_accepted() {
+        let today = Utc::now().date_naive();
+
+        assert_ok!(StayRange::parse(today, today + Days::new(1)));
+        assert_ok!(StayRange::parse(
+            today + Days::new(30),
+            today + Days::new(45)
+        ));
+    }
 }
@@ -0,0 +1,281 @@
+use chrono::NaiveDate;
+
+/// Longest free cancellation window, in days before check-in
+pub const MAX_FREE_CANCELLATION_DAYS: u32 = 365;
+
+// Terms under which a guest may cancel, attached to rooms and copied to
+// bookings when they are made
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CancellationPolicy {
+    // Free until `free_until_days` before check-in, `penalty_percent` of the
+    // total is charged afterwards
+    Refundable {
+        free_until_days: u32,
+        penalty_percent: u8,
+    },
+    NonRefundable,
+}
+
+impl Default for CancellationPolicy {
+    fn default() -> Self {
+        CancellationPolicy::Refundable {
+            free_until_days: 0,
+            penalty_percent: 0,
+        }
+    }
+}
+
+/// What a cancellation costs the guest, and what they get back, in the
+/// smallest unit of the currency
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CancellationCharges {
+    pub penalty: i64,
+    pub refund: i64,
+}
+
+impl CancellationPolicy {
+    pub fn parse(policy: CancellationPolicy) -> Result<Self, String> {
+        match policy {
+            CancellationPolicy::Refundable {
+                penalty_percent, ..
+            } if penalty_percent > 100 => Err(format!(
+                "A cancellation penalty of {}% is more than the total",
+                penalty_percent
+            )),
+            CancellationPolicy::Refundable {
+                free_until_days, ..
+            } if free_until_days > MAX_FREE_CANCELLATION_DAYS => Err(format!(
+                "Free cancellation cannot last more than {} days before check-in",
+                MAX_FREE_CANCELLATION_DAYS
+            )),
+            policy => Ok(policy),
+        }
+    }
+
+    /// Restore a policy from the `cancellation_policy`, `free_cancellation_days`
+    /// and `cancellation_penalty_percent` columns.
+    ///
+    /// Policies stored before free cancellation was bounded are restored as
+    /// they are.
+    pub fn from_columns(
+        kind: &str,
+        free_until_days: i32,
+        penalty_percent: i16,
+    ) -> Result<Self, String> {
+        match kind {
+            "refundable" => Ok(CancellationPolicy::Refundable {
+                free_until_days: u32::try_from(free_until_days)
+                    .map_err(|_| format!("{} is not a valid number of days", free_until_days))?,
+                penalty_percent: u8::try_from(penalty_percent)
+                    .ok()
+                    .filter(|percent| *percent <= 100)
+                    .ok_or_else(|| format!("{} is not a valid percentage", penalty_percent))?,
+            }),
+            "non_refundable" => Ok(CancellationPolicy::NonRefundable),
+            _ => Err(format!("{} is not a valid cancellation policy!", kind)),
+        }
+    }
+
+    pub fn free_until_days(&self) -> i32 {
+        match self {
+            CancellationPolicy::Refundable {
+                free_until_days, ..
+            } => *free_until_days as i32,
+            CancellationPolicy::NonRefundable => 0,
+        }
+    }
+
+    pub fn penalty_percent(&self) -> i16 {
+        match self {
+            CancellationPolicy::Refundable {
+                penalty_percent, ..
+            } => *penalty_percent as i16,
+            CancellationPolicy::NonRefundable => 100,
+        }
+    }
+
+    /// Charges of cancelling, on the given day, a stay paid `total_price`.
+    ///
+    /// Cancelling exactly `free_until_days` before check-in is still free.
+    /// Penalties are rounded to the nearest unit.
+    pub fn evaluate(
+        &self,
+        total_price: i64,
+        check_in: NaiveDate,
+        cancelled_on: NaiveDate,
+    ) -> CancellationCharges {
+        let penalty = match self {
+            CancellationPolicy::Refundable {
+                free_until_days,
+                penalty_percent,
+            } => {
+                let days_before_check_in = (check_in - cancelled_on).num_days();
+                let penalty_percent = *penalty_percent as i64;
+                if days_before_check_in >= *free_until_days as i64 {
+                    0
+                } else {
+                    // A total too large to multiply is divided first, which
+                    // only loses the rounding
+                    total_price
+                        .checked_mul(penalty_percent)
+                        .and_then(|penalty| penalty.checked_add(50))
+                        .map_or(total_price / 100 * penalty_percent, |penalty| penalty / 100)
+                }
+            }
+            CancellationPolicy::NonRefundable => total_price,
+        };
+
+        CancellationCharges {
+            penalty,
+            refund: total_price - penalty,
+        }
+    }
+}
+
+impl AsRef<str> for CancellationPolicy {
+    fn as_ref(&self) -> &str {
+        match self {
+            CancellationPolicy::Refundable { .. } => "refundable",
+            CancellationPolicy::NonRefundable => "non_refundable",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate};
+    use claims::{assert_err, assert_ok};
+
+    use super::{CancellationCharges, CancellationPolicy, MAX_FREE_CANCELLATION_DAYS};
+
+    fn check_in() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 12, 20).unwrap()
+    }
+
+    #[test]
+    fn cancellation_charges_follow_the_policy() {
+        let refundable = CancellationPolicy::Refundable {
+            free_until_days: 7,
+            penalty_percent: 50,
+        };
+        let test_cases = vec![
+            (refundable, 30, 0, "a month before with a refundable policy"),
+            (refundable, 7, 0, "on the last free day"),
+            (refundable, 6, 5_000, "the day after the last free day"),
+            (refundable, 0, 5_000, "on the check-in day"),
+            (
+                CancellationPolicy::default(),
+                0,
+                0,
+                "on the check-in day with the default policy",
+            ),
+            (
+                CancellationPolicy::NonRefundable,
+                30,
+                10_000,
+                "a month before with a non-refundable policy",
+            ),
+        ];
+
+        for (policy, days_before, penalty, error_message) in test_cases {
+            let cancelled_on = check_in() - Days::new(days_before);
+
+            assert_eq!(
+                policy.evaluate(10_000, check_in(), cancelled_on),
+                CancellationCharges {
+                    penalty,
+                    refund: 10_000 - penalty,
+                },
+                "Unexpected charges when cancelling {}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn cancellation_penalty_is_rounded_to_the_nearest_unit() {
+        let policy = CancellationPolicy::Refundable {
+            free_until_days: 1,
+            penalty_percent: 15,
+        };
+        let test_cases = vec![(999, 150), (1_003, 150), (1_004, 151)];
+
+        for (total_price, penalty) in test_cases {
+            let charges = policy.evaluate(total_price, check_in(), check_in());
+
+            assert_eq!(charges.penalty, penalty);
+            assert_eq!(charges.refund, total_price - penalty);
+        }
+    }
+
+    #[test]
+    fn cancellation_penalty_of_a_huge_total_does_not_overflow() {
+        let policy = CancellationPolicy::Refundable {
+            free_until_days: 1,
+            penalty_percent: 50,
+        };
+
+        let charges = policy.evaluate(i64::MAX, check_in(), check_in());
+
+        assert_eq!(charges.penalty, i64::MAX / 100 * 50);
+        assert_eq!(charges.refund, i64::MAX - charges.penalty);
+    }
+
+    #[test]
+    fn invalid_cancellation_policy_is_rejected() {
+        let test_cases = vec![
+            (("refundable", 7, 101), "a penalty above 100%"),
+            (("refundable", -1, 50), "negative days"),
+            (("refundable", 7, -5), "a negative penalty"),
+            (("flexible", 7, 50), "an unknown kind"),
+        ];
+
+        for ((kind, free_until_days, penalty_percent), error_message) in test_cases {
+            assert!(
+                CancellationPolicy::from_columns(kind, free_until_days, penalty_percent).is_err(),
+                "The cancellation policy was accepted with {}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn valid_cancellation_policy_is_accepted() {
+        let policies = vec![
+            CancellationPolicy::default(),
+            CancellationPolicy::Refundable {
+                free_until_days: 14,
+                penalty_percent: 100,
+            },
+            CancellationPolicy::NonRefundable,
+        ];
+
+        for policy in policies {
+            let restored = CancellationPolicy::from_columns(
+                policy.as_ref(),
+                policy.free_until_days(),
+                policy.penalty_percent(),
+            );
+
+            assert_eq!(restored, Ok(policy));
+        }
+        assert_err!(CancellationPolicy::parse(CancellationPolicy::Refundable {
+            free_until_days: 0,
+            penalty_percent: 200,
+        }));
+        assert_err!(CancellationPolicy::parse(CancellationPolicy::Refundable {
+            free_until_days: MAX_FREE_CANCELLATION_DAYS + 1,
+            penalty_percent: 50,
+        }));
+        assert_err!(CancellationPolicy::parse(CancellationPolicy::Refundable {
+            free_until_days: u32::MAX,
+            penalty_percent: 50,
+        }));
+        assert_ok!(CancellationPolicy::parse(CancellationPolicy::Refundable {
+            free_until_days: MAX_FREE_CANCELLATION_DAYS,
+            penalty_percent: 50,
+        }));
+        assert_ok!(CancellationPolicy::parse(CancellationPolicy::NonRefundable));
+    }
+}
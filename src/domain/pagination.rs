@@ -0,0 +1,81 @@
+const MAX_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    page: u32,
+    per_page: u32,
+}
+
+impl Pagination {
+    pub fn parse(page: u32, per_page: u32) -> Result<Pagination, String> {
+        if page == 0 {
+            Err("Page starts from 1".to_string())
+        } else if per_page == 0 || per_page > MAX_PER_PAGE {
+            Err(format!(
+                "Items per page must be between 1 and {}",
+                MAX_PER_PAGE
+            ))
+        } else {
+            Ok(Self { page, per_page })
+        }
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page as i64
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page as i64 - 1) * self.per_page as i64
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, pagination: &Pagination, total: u64) -> Self {
+        Self {
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_ok;
+
+    use super::Pagination;
+
+    #[test]
+    fn invalid_pagination_is_rejected() {
+        let test_cases = vec![
+            (0, 10, "page is zero"),
+            (1, 0, "no items per page"),
+            (1, 101, "too many items per page"),
+        ];
+
+        for (page, per_page, error_message) in test_cases {
+            assert!(
+                Pagination::parse(page, per_page).is_err(),
+                "The pagination was accepted when {}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn offset_skips_previous_pages() {
+        let pagination = assert_ok!(Pagination::parse(3, 20));
+
+        assert_eq!(pagination.limit(), 20);
+        assert_eq!(pagination.offset(), 40);
+    }
+}
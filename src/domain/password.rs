@@ -0,0 +1,72 @@
+use secrecy::{ExposeSecret, Secret};
+use unicode_segmentation::UnicodeSegmentation;
+
+const MIN_PASSWORD_LENGTH: usize = 12;
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// A new password that follows our password policy
+#[derive(Debug)]
+pub struct UserPassword(Secret<String>);
+
+impl UserPassword {
+    pub fn parse(s: Secret<String>) -> Result<UserPassword, String> {
+        let length = s.expose_secret().graphemes(true).count();
+        let is_blank = s.expose_secret().trim().is_empty();
+
+        if is_blank || length < MIN_PASSWORD_LENGTH {
+            Err(format!(
+                "The password must be at least {} characters long",
+                MIN_PASSWORD_LENGTH
+            ))
+        } else if length > MAX_PASSWORD_LENGTH {
+            Err(format!(
+                "The password must be at most {} characters long",
+                MAX_PASSWORD_LENGTH
+            ))
+        } else {
+            Ok(Self(s))
+        }
+    }
+}
+
+impl From<UserPassword> for Secret<String> {
+    fn from(value: UserPassword) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+
+    use super::UserPassword;
+
+    #[test]
+    fn short_or_blank_passwords_are_rejected() {
+        let too_long = "a".repeat(129);
+        let test_cases = vec![
+            ("", "is empty"),
+            ("correcthors", "is 11 characters long"),
+            ("            ", "is blank"),
+            (too_long.as_str(), "is 129 characters long"),
+        ];
+
+        for (password, error_message) in test_cases {
+            assert_err!(
+                UserPassword::parse(Secret::new(password.to_string())),
+                "{}",
+                error_message
+            );
+        }
+    }
+
+    #[test]
+    fn long_enough_passwords_are_accepted() {
+        let test_cases = vec!["correcthorse".to_string(), "🦀".repeat(12), "a".repeat(128)];
+
+        for password in test_cases {
+            assert_ok!(UserPassword::parse(Secret::new(password)));
+        }
+    }
+}
@@ -1,5 +1,25 @@
-use super::Room;
+use uuid::Uuid;
+
+use super::{Host, HostCategory, Page, Pagination, Room, StayRange};
 
 pub trait RoomRepository {
-    async fn find_all(&self, hotel_id: u16) -> Result<Option<Vec<Room>>, String>;
+    async fn find_all(&self, host_id: Uuid) -> Result<Vec<Room>, anyhow::Error>;
+    /// Rooms of the host that fit the guests and have no booking overlapping the stay
+    async fn find_available(
+        &self,
+        host_id: Uuid,
+        stay: &StayRange,
+        guests: u16,
+    ) -> Result<Vec<Room>, anyhow::Error>;
+}
+
+pub trait HostRepository {
+    async fn find_by_id(&self, host_id: Uuid) -> Result<Option<Host>, anyhow::Error>;
+    /// Only the hosts in `host_ids` when it is given
+    async fn find_all(
+        &self,
+        category: Option<&HostCategory>,
+        host_ids: Option<&[Uuid]>,
+        pagination: &Pagination,
+    ) -> Result<Page<Host>, anyhow::Error>;
 }
@@ -2,93 +2,248 @@ use std::marker::PhantomData;
 
 use uuid::Uuid;
 
-trait BookingState {}
+mod private {
+    pub trait Sealed {}
+}
 
-#[derive(Debug)]
-struct UserId(String);
+/// A state of the booking lifecycle, sealed so that no state can be added
+/// outside of this module
+pub trait BookingState: private::Sealed {
+    const STATUS: BookingStatus;
+}
 
-#[derive(Debug)]
-pub struct Session<State: BookingState = Initial> {
-    pub id: String,
-    pub user_id: Option<UserId>,
-    pub marker: PhantomData<State>,
+/// Statuses of a booking as stored in the `bookings` table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookingStatus {
+    Pending,
+    Confirmed,
+    CheckedIn,
+    CheckedOut,
+    Cancelled,
+    NoShow,
 }
 
-impl Session {
-    pub fn new() -> Session<Initial> {
-        let id = Uuid::new_v4().to_string();
-        Session::<Initial> {
-            id,
-            user_id: None,
-            marker: PhantomData,
+impl BookingStatus {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(BookingStatus::Pending),
+            "confirmed" => Ok(BookingStatus::Confirmed),
+            "checked_in" => Ok(BookingStatus::CheckedIn),
+            "checked_out" => Ok(BookingStatus::CheckedOut),
+            "cancelled" => Ok(BookingStatus::Cancelled),
+            "no_show" => Ok(BookingStatus::NoShow),
+            _ => Err(format!("{} is not a valid booking status!", s)),
         }
     }
 }
 
-impl Session<Initial> {
-    pub fn checkin(self) -> Session<Ongoing> {
-        Session::<Ongoing> {
-            id: self.id,
-            user_id: Some(UserId("test".into())),
-            marker: PhantomData,
+impl AsRef<str> for BookingStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            BookingStatus::Pending => "pending",
+            BookingStatus::Confirmed => "confirmed",
+            BookingStatus::CheckedIn => "checked_in",
+            BookingStatus::CheckedOut => "checked_out",
+            BookingStatus::Cancelled => "cancelled",
+            BookingStatus::NoShow => "no_show",
         }
     }
 }
 
-impl Session<Ongoing> {
-    pub fn finish(self) -> Session<Done> {
-        Session::<Done> {
+/// A booking in a given state of its lifecycle.
+///
+/// Only the legal transitions are implemented, so an illegal one does not
+/// compile, e.g. there is no `check_out` on a `Booking<Pending>`.
+#[derive(Debug)]
+pub struct Booking<State: BookingState = Pending> {
+    id: Uuid,
+    marker: PhantomData<State>,
+}
+
+impl<State: BookingState> Booking<State> {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn status(&self) -> BookingStatus {
+        State::STATUS
+    }
+
+    fn transition<Next: BookingState>(self) -> Booking<Next> {
+        Booking {
             id: self.id,
-            user_id: self.user_id,
             marker: PhantomData,
         }
     }
+}
 
-    pub fn cancel(self) -> Session<Cancelled> {
-        Session::<Cancelled> {
-            id: self.id,
-            user_id: self.user_id,
+impl Booking {
+    pub fn new(id: Uuid) -> Booking<Pending> {
+        Booking {
+            id,
             marker: PhantomData,
         }
     }
 }
 
-impl Session<Done> {
-    pub fn completed(self) -> bool {
-        true
+impl Booking<Pending> {
+    pub fn confirm(self) -> Booking<Confirmed> {
+        self.transition()
+    }
+
+    pub fn cancel(self) -> Booking<Cancelled> {
+        self.transition()
     }
 }
 
-impl Session<Cancelled> {
-    pub fn cancelled(self) -> bool {
-        true
+impl Booking<Confirmed> {
+    pub fn check_in(self) -> Booking<CheckedIn> {
+        self.transition()
+    }
+
+    pub fn cancel(self) -> Booking<Cancelled> {
+        self.transition()
+    }
+
+    pub fn mark_no_show(self) -> Booking<NoShow> {
+        self.transition()
+    }
+}
+
+impl Booking<CheckedIn> {
+    pub fn check_out(self) -> Booking<CheckedOut> {
+        self.transition()
     }
 }
 
 #[derive(Debug, Default)]
-struct Initial;
+pub struct Pending;
 #[derive(Debug, Default)]
-struct Ongoing;
+pub struct Confirmed;
 #[derive(Debug, Default)]
-struct Cancelled;
+pub struct CheckedIn;
 #[derive(Debug, Default)]
-struct Done;
+pub struct CheckedOut;
+#[derive(Debug, Default)]
+pub struct Cancelled;
+#[derive(Debug, Default)]
+pub struct NoShow;
 
-impl BookingState for Initial {}
-impl BookingState for Ongoing {}
-impl BookingState for Cancelled {}
-impl BookingState for Done {}
+macro_rules! booking_state {
+    ($state:ident, $variant:ident) => {
+        impl private::Sealed for $state {}
+
+        impl BookingState for $state {
+            const STATUS: BookingStatus = BookingStatus::$variant;
+        }
+
+        impl From<Booking<$state>> for BookingLifecycle {
+            fn from(booking: Booking<$state>) -> Self {
+                BookingLifecycle::$variant(booking)
+            }
+        }
+    };
+}
+
+booking_state!(Pending, Pending);
+booking_state!(Confirmed, Confirmed);
+booking_state!(CheckedIn, CheckedIn);
+booking_state!(CheckedOut, CheckedOut);
+booking_state!(Cancelled, Cancelled);
+booking_state!(NoShow, NoShow);
+
+/// A booking loaded from the database, whose state is only known at runtime.
+///
+/// Match on it to get the typed [`Booking`] and apply a transition.
+#[derive(Debug)]
+pub enum BookingLifecycle {
+    Pending(Booking<Pending>),
+    Confirmed(Booking<Confirmed>),
+    CheckedIn(Booking<CheckedIn>),
+    CheckedOut(Booking<CheckedOut>),
+    Cancelled(Booking<Cancelled>),
+    NoShow(Booking<NoShow>),
+}
+
+impl BookingLifecycle {
+    /// Restore a stored booking in its stored status
+    pub fn restore(id: Uuid, status: BookingStatus) -> Self {
+        let booking = Booking::new(id);
+        match status {
+            BookingStatus::Pending => booking.into(),
+            BookingStatus::Confirmed => booking.transition::<Confirmed>().into(),
+            BookingStatus::CheckedIn => booking.transition::<CheckedIn>().into(),
+            BookingStatus::CheckedOut => booking.transition::<CheckedOut>().into(),
+            BookingStatus::Cancelled => booking.transition::<Cancelled>().into(),
+            BookingStatus::NoShow => booking.transition::<NoShow>().into(),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            BookingLifecycle::Pending(booking) => booking.id(),
+            BookingLifecycle::Confirmed(booking) => booking.id(),
+            BookingLifecycle::CheckedIn(booking) => booking.id(),
+            BookingLifecycle::CheckedOut(booking) => booking.id(),
+            BookingLifecycle::Cancelled(booking) => booking.id(),
+            BookingLifecycle::NoShow(booking) => booking.id(),
+        }
+    }
+
+    pub fn status(&self) -> BookingStatus {
+        match self {
+            BookingLifecycle::Pending(booking) => booking.status(),
+            BookingLifecycle::Confirmed(booking) => booking.status(),
+            BookingLifecycle::CheckedIn(booking) => booking.status(),
+            BookingLifecycle::CheckedOut(booking) => booking.status(),
+            BookingLifecycle::Cancelled(booking) => booking.status(),
+            BookingLifecycle::NoShow(booking) => booking.status(),
+        }
+    }
+}
 
 #[cfg(test)]
-mod test {
-    use super::Session;
+mod tests {
+    use claims::{assert_err, assert_ok};
+    use uuid::Uuid;
+
+    use super::{Booking, BookingLifecycle, BookingStatus};
+
+    #[test]
+    fn booking_goes_through_its_lifecycle() {
+        let id = Uuid::new_v4();
+        let booking = Booking::new(id);
+        assert_eq!(booking.status(), BookingStatus::Pending);
+
+        let booking = booking.confirm().check_in().check_out();
+
+        assert_eq!(booking.status(), BookingStatus::CheckedOut);
+        assert_eq!(booking.id(), id);
+    }
 
     #[test]
-    fn check_api() {
-        let session = Session::new();
-        let ongoing_session = session.checkin();
-        let done = ongoing_session.finish();
+    fn restored_booking_keeps_its_status() {
+        let test_cases = vec![
+            BookingStatus::Pending,
+            BookingStatus::Confirmed,
+            BookingStatus::CheckedIn,
+            BookingStatus::CheckedOut,
+            BookingStatus::Cancelled,
+            BookingStatus::NoShow,
+        ];
 
-        assert!(done.completed());
+        for status in test_cases {
+            let booking = BookingLifecycle::restore(Uuid::new_v4(), status);
+
+            assert_eq!(booking.status(), status);
+            assert_eq!(BookingStatus::parse(status.as_ref()).unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn invalid_booking_status_is_rejected() {
+        assert_err!(BookingStatus::parse("checked-in"));
+        assert_ok!(BookingStatus::parse("checked_in"));
     }
 }
@@ -1,3 +1,5 @@
-mod room_repository_impl;
+mod pg_host_repository;
+mod pg_room_repository;
 
-pub use room_repository_impl::*;
+pub use pg_host_repository::*;
+pub use pg_room_repository::*;
@@ -0,0 +1,103 @@
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{GeneralName, Host, HostCategory, HostRepository, Page, Pagination};
+
+pub struct PgHostRepository {
+    pool: PgPool,
+}
+
+impl PgHostRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+struct HostRow {
+    id: Uuid,
+    name: String,
+    category: String,
+}
+
+impl TryFrom<HostRow> for Host {
+    type Error = anyhow::Error;
+
+    fn try_from(row: HostRow) -> Result<Self, Self::Error> {
+        Ok(Host {
+            id: row.id,
+            category: HostCategory::parse(&row.category).map_err(anyhow::Error::msg)?,
+            name: GeneralName::parse(row.name).map_err(anyhow::Error::msg)?,
+        })
+    }
+}
+
+impl HostRepository for PgHostRepository {
+    #[tracing::instrument(name = "Query host by id in database", skip(self))]
+    async fn find_by_id(&self, host_id: Uuid) -> Result<Option<Host>, anyhow::Error> {
+        let row = sqlx::query_as!(
+            HostRow,
+            r#"
+            SELECT id, name, category
+            FROM hosts
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            host_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to perform a query to retrieve host.")?;
+
+        row.map(Host::try_from).transpose()
+    }
+
+    #[tracing::instrument(name = "Query page of hosts in database", skip(self))]
+    async fn find_all(
+        &self,
+        category: Option<&HostCategory>,
+        host_ids: Option<&[Uuid]>,
+        pagination: &Pagination,
+    ) -> Result<Page<Host>, anyhow::Error> {
+        let category = category.map(|c| c.as_ref());
+        let rows = sqlx::query_as!(
+            HostRow,
+            r#"
+            SELECT id, name, category
+            FROM hosts
+            WHERE deleted_at IS NULL
+                AND ($1::TEXT IS NULL OR category = $1)
+                AND ($2::UUID[] IS NULL OR id = ANY($2))
+            ORDER BY name, id
+            LIMIT $3 OFFSET $4
+            "#,
+            category,
+            host_ids,
+            pagination.limit(),
+            pagination.offset(),
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to perform a query to retrieve hosts.")?;
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "total!"
+            FROM hosts
+            WHERE deleted_at IS NULL
+                AND ($1::TEXT IS NULL OR category = $1)
+                AND ($2::UUID[] IS NULL OR id = ANY($2))
+            "#,
+            category,
+            host_ids,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to perform a query to count hosts.")?;
+
+        let hosts = rows
+            .into_iter()
+            .map(Host::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page::new(hosts, pagination, total as u64))
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{
+    CancellationPolicy, GeneralName, Host, HostCategory, Room, RoomRepository, StayRange,
+};
+
+pub struct PgRoomRepository {
+    pool: PgPool,
+}
+
+impl PgRoomRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+// A room joined with its host
+struct RoomRow {
+    id: Uuid,
+    name: String,
+    description: String,
+    number_of_beds: i16,
+    price_per_night: i64,
+    cancellation_policy: String,
+    free_cancellation_days: i32,
+    cancellation_penalty_percent: i16,
+    host_id: Uuid,
+    host_name: String,
+    host_category: String,
+}
+
+impl TryFrom<RoomRow> for Room {
+    type Error = anyhow::Error;
+
+    fn try_from(row: RoomRow) -> Result<Self, Self::Error> {
+        Ok(Room {
+            id: row.id,
+            container: Host {
+                id: row.host_id,
+                category: HostCategory::parse(&row.host_category).map_err(anyhow::Error::msg)?,
+                name: GeneralName::parse(row.host_name).map_err(anyhow::Error::msg)?,
+            },
+            name: GeneralName::parse(row.name).map_err(anyhow::Error::msg)?,
+            description: row.description,
+            number_of_beds: row.number_of_beds as u16,
+            price_per_night: row.price_per_night,
+            cancellation_policy: CancellationPolicy::from_columns(
+                &row.cancellation_policy,
+                row.free_cancellation_days,
+                row.cancellation_penalty_percent,
+            )
+            .map_err(anyhow::Error::msg)?,
+        })
+    }
+}
+
+impl RoomRepository for PgRoomRepository {
+    #[tracing::instrument(name = "Query all rooms of host in database", skip(self))]
+    async fn find_all(&self, host_id: Uuid) -> Result<Vec<Room>, anyhow::Error> {
+        let rows = sqlx::query_as!(
+            RoomRow,
+            r#"
+            SELECT r.id, r.name, r.description, r.number_of_beds, r.price_per_night,
+                r.cancellation_policy, r.free_cancellation_days, r.cancellation_penalty_percent,
+                h.id AS host_id, h.name AS host_name, h.category AS host_category
+            FROM rooms r
+            JOIN hosts h ON h.id = r.host_id
+            WHERE r.host_id = $1 AND r.deleted_at IS NULL
+            ORDER BY r.name
+            "#,
+            host_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to perform a query to retrieve rooms.")?;
+
+        rows.into_iter().map(Room::try_from).collect()
+    }
+
+    #[tracing::instrument(name = "Query available rooms in database", skip(self))]
+    async fn find_available(
+        &self,
+        host_id: Uuid,
+        stay: &StayRange,
+        guests: u16,
+    ) -> Result<Vec<Room>, anyhow::Error> {
+        let rows = sqlx::query_as!(
+            RoomRow,
+            r#"
+            SELECT r.id, r.name, r.description, r.number_of_beds, r.price_per_night,
+                r.cancellation_policy, r.free_cancellation_days, r.cancellation_penalty_percent,
+                h.id AS host_id, h.name AS host_name, h.category AS host_category
+            FROM rooms r
+            JOIN hosts h ON h.id = r.host_id
+            WHERE r.host_id = $1
+                AND r.deleted_at IS NULL
+                AND r.number_of_beds >= $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM bookings b
+                    WHERE b.room_id = r.id
+                        AND b.status <> 'cancelled'
+                        AND daterange(b.check_in, b.check_out, '[)') && daterange($3, $4, '[)')
+                )
+            ORDER BY r.number_of_beds, r.name
+            "#,
+            host_id,
+            guests as i16,
+            stay.check_in(),
+            stay.check_out(),
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to perform a query to retrieve available rooms.")?;
+
+        rows.into_iter().map(Room::try_from).collect()
+    }
+}
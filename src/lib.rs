@@ -1,6 +1,7 @@
 pub mod authentication;
 pub mod configuration;
 mod domain;
+pub mod email_client;
 mod infrastructure;
 mod routes;
 mod services;
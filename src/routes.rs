@@ -1,9 +1,27 @@
 mod admin;
+mod booking;
+mod did_login;
+mod jwks;
 mod login;
+mod logout;
+mod password;
+mod register;
+mod room;
+mod token;
+mod totp;
 
 use actix_web::{get, HttpResponse};
 pub use admin::*;
+pub use booking::*;
+pub use did_login::*;
+pub use jwks::*;
 pub use login::*;
+pub use logout::*;
+pub use password::*;
+pub use register::*;
+pub use room::*;
+pub use token::*;
+pub use totp::*;
 
 #[get("/health_check")]
 pub async fn health_check() -> Result<HttpResponse, actix_web::Error> {
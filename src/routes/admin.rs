@@ -1,7 +1,9 @@
+mod api_key;
+mod booking;
 mod host;
 mod room;
-mod user;
 
+pub use api_key::*;
+pub use booking::*;
 pub use host::*;
 pub use room::*;
-pub use user::*;
@@ -1,7 +1,7 @@
-mod get;
+mod delete;
 mod list;
 mod post;
 
-pub use get::*;
+pub use delete::*;
 pub use list::*;
 pub use post::*;
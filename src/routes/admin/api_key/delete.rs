@@ -0,0 +1,74 @@
+use actix_web::{delete, http::header::ContentType, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    authentication::{revoke_api_key, AuthenticatedUser, RequireScope, Scope},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    api_key_id: Uuid,
+}
+
+#[derive(thiserror::Error)]
+pub enum DeleteApiKeyError {
+    #[error("API key {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Only admins can revoke API keys")]
+    Forbidden,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for DeleteApiKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for DeleteApiKeyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteApiKeyError::NotFound(_) => StatusCode::NOT_FOUND,
+            DeleteApiKeyError::Forbidden => StatusCode::FORBIDDEN,
+            DeleteApiKeyError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Revoke an API key, it is kept so that it still shows up when listing keys
+#[tracing::instrument(
+    name = "Revoke an API key"
+    skip(info, pool, user),
+    fields(api_key_id=%info.api_key_id, user_id=%user.user_id)
+)]
+#[delete(
+    "/api-keys/{api_key_id}",
+    wrap = "RequireScope::new(Scope::UsersAdmin)"
+)]
+pub async fn delete_api_keys(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, DeleteApiKeyError> {
+    let Info { api_key_id } = info.into_inner();
+    if !user.is_admin() {
+        return Err(DeleteApiKeyError::Forbidden);
+    }
+    if !revoke_api_key(api_key_id, &pool).await? {
+        return Err(DeleteApiKeyError::NotFound(api_key_id));
+    }
+
+    let data = ResponseData {
+        data: api_key_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully revoked API key {}", api_key_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
@@ -0,0 +1,58 @@
+use actix_web::{get, http::header::ContentType, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+
+use crate::{
+    authentication::{get_api_keys, AuthenticatedUser, RequireScope, Scope},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(thiserror::Error)]
+pub enum ListApiKeysError {
+    #[error("Only admins can list API keys")]
+    Forbidden,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ListApiKeysError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ListApiKeysError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ListApiKeysError::Forbidden => StatusCode::FORBIDDEN,
+            ListApiKeysError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// List every API key, revoked ones included, without their secrets
+#[tracing::instrument(
+    name = "Get list of API keys",
+    skip(pool, user),
+    fields(user_id=%user.user_id)
+)]
+#[get("/api-keys", wrap = "RequireScope::new(Scope::UsersAdmin)")]
+pub async fn list_api_keys(
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, ListApiKeysError> {
+    if !user.is_admin() {
+        return Err(ListApiKeysError::Forbidden);
+    }
+    let api_keys = get_api_keys(&pool).await?;
+
+    let data = ResponseData {
+        data: api_keys,
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully retrieved API keys".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
@@ -0,0 +1,144 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    authentication::{
+        create_api_key, ApiKey, AuthenticatedUser, NewApiKey, RequireScope, Role, Scope,
+    },
+    domain::GeneralName,
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    name: String,
+    owner_id: Option<Uuid>,
+    scope: String,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CreatedApiKey {
+    api_key: ApiKey,
+    key: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum PostApiKeyError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Only admins can create API keys")]
+    Forbidden,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PostApiKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PostApiKeyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PostApiKeyError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PostApiKeyError::Forbidden => StatusCode::FORBIDDEN,
+            PostApiKeyError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Create an API key acting as its owner, the requesting admin by default.
+///
+/// The plaintext key is only part of this response, it cannot be retrieved later.
+#[tracing::instrument(
+    name = "Create an API key"
+    skip(pool, body, user),
+    fields(user_id=%user.user_id)
+)]
+#[post("/api-keys", wrap = "RequireScope::new(Scope::UsersAdmin)")]
+pub async fn add_api_keys(
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, PostApiKeyError> {
+    if !user.is_admin() {
+        return Err(PostApiKeyError::Forbidden);
+    }
+    let BodyData {
+        name,
+        owner_id,
+        scope,
+        expires_at,
+    } = body.0;
+    let name = GeneralName::parse(name).map_err(PostApiKeyError::ValidationError)?;
+    let scopes = Scope::parse_list(&scope).map_err(PostApiKeyError::ValidationError)?;
+    if scopes.is_empty() {
+        return Err(PostApiKeyError::ValidationError(
+            "An API key needs at least one scope".to_string(),
+        ));
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(PostApiKeyError::ValidationError(
+            "The expiry of an API key must be in the future".to_string(),
+        ));
+    }
+    let owner_id = owner_id.unwrap_or(user.user_id);
+    let owner_role = get_owner_role(owner_id, &pool).await?.ok_or_else(|| {
+        PostApiKeyError::ValidationError(format!("User {} does not exist", owner_id))
+    })?;
+    if let Some(scope) = scopes
+        .iter()
+        .find(|scope| !owner_role.scopes().contains(scope))
+    {
+        return Err(PostApiKeyError::ValidationError(format!(
+            "A {} cannot be granted the {} scope",
+            owner_role.as_ref(),
+            scope.as_ref()
+        )));
+    }
+
+    let new_api_key = NewApiKey {
+        owner_id,
+        name: name.as_ref().to_string(),
+        scopes,
+        expires_at,
+    };
+    let (api_key, key) = create_api_key(&new_api_key, &pool).await?;
+
+    let data = ResponseData {
+        data: CreatedApiKey {
+            api_key,
+            key: key.expose_secret().to_string(),
+        },
+        code: StatusCode::OK.as_u16(),
+        message: format!(
+            "Successfully created API key {}, store it now as it will not be shown again",
+            name.as_ref()
+        ),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+#[tracing::instrument(name = "Get role of API key owner", skip(pool))]
+async fn get_owner_role(owner_id: Uuid, pool: &PgPool) -> Result<Option<Role>, anyhow::Error> {
+    let role = sqlx::query_scalar!(
+        r#"SELECT role FROM users WHERE user_id = $1 AND status = 'active'"#,
+        owner_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the owner of the API key.")?;
+
+    role.map(|role| Role::parse(&role).map_err(|e| anyhow::anyhow!(e)))
+        .transpose()
+}
@@ -0,0 +1,286 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{BookingLifecycle, BookingStatus},
+    routes::{cancel_booking, CancelBookingError},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    booking_id: Uuid,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct BookingStatusData {
+    pub id: Uuid,
+    pub status: BookingStatus,
+}
+
+#[derive(thiserror::Error)]
+pub enum BookingTransitionError {
+    #[error("Booking {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Booking {0} is {1}, it cannot be {2}")]
+    Conflict(Uuid, String, &'static str),
+    #[error("Booking {0} belongs to a host you do not manage")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    Cancellation(#[from] CancelBookingError),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for BookingTransitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for BookingTransitionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BookingTransitionError::NotFound(_) => StatusCode::NOT_FOUND,
+            BookingTransitionError::Conflict(..) => StatusCode::CONFLICT,
+            BookingTransitionError::Forbidden(_) => StatusCode::FORBIDDEN,
+            BookingTransitionError::Cancellation(e) => e.status_code(),
+            BookingTransitionError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Confirm a booking"
+    skip(info, pool, user),
+    fields(booking_id=%info.booking_id, user_id=%user.user_id)
+)]
+#[post(
+    "/bookings/{booking_id}/confirm",
+    wrap = "RequireScope::new(Scope::BookingsWrite)"
+)]
+pub async fn confirm_bookings(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    transition_booking(
+        info.booking_id,
+        &pool,
+        &user,
+        "confirmed",
+        |booking| match booking {
+            BookingLifecycle::Pending(booking) => Ok(booking.confirm().into()),
+            booking => Err(booking),
+        },
+    )
+    .await
+}
+
+#[tracing::instrument(
+    name = "Check a booking in"
+    skip(info, pool, user),
+    fields(booking_id=%info.booking_id, user_id=%user.user_id)
+)]
+#[post(
+    "/bookings/{booking_id}/check-in",
+    wrap = "RequireScope::new(Scope::BookingsWrite)"
+)]
+pub async fn check_in_bookings(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    transition_booking(
+        info.booking_id,
+        &pool,
+        &user,
+        "checked in",
+        |booking| match booking {
+            BookingLifecycle::Confirmed(booking) => Ok(booking.check_in().into()),
+            booking => Err(booking),
+        },
+    )
+    .await
+}
+
+#[tracing::instrument(
+    name = "Check a booking out"
+    skip(info, pool, user),
+    fields(booking_id=%info.booking_id, user_id=%user.user_id)
+)]
+#[post(
+    "/bookings/{booking_id}/check-out",
+    wrap = "RequireScope::new(Scope::BookingsWrite)"
+)]
+pub async fn check_out_bookings(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    transition_booking(
+        info.booking_id,
+        &pool,
+        &user,
+        "checked out",
+        |booking| match booking {
+            BookingLifecycle::CheckedIn(booking) => Ok(booking.check_out().into()),
+            booking => Err(booking),
+        },
+    )
+    .await
+}
+
+#[tracing::instrument(
+    name = "Mark a booking as a no-show"
+    skip(info, pool, user),
+    fields(booking_id=%info.booking_id, user_id=%user.user_id)
+)]
+#[post(
+    "/bookings/{booking_id}/no-show",
+    wrap = "RequireScope::new(Scope::BookingsWrite)"
+)]
+pub async fn no_show_bookings(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    transition_booking(
+        info.booking_id,
+        &pool,
+        &user,
+        "marked as a no-show",
+        |booking| match booking {
+            BookingLifecycle::Confirmed(booking) => Ok(booking.mark_no_show().into()),
+            booking => Err(booking),
+        },
+    )
+    .await
+}
+
+/// Cancel a booking the same way guests do, its cancellation policy is
+/// applied and recorded
+#[tracing::instrument(
+    name = "Cancel a booking"
+    skip(info, pool, user),
+    fields(booking_id=%info.booking_id, user_id=%user.user_id)
+)]
+#[post(
+    "/bookings/{booking_id}/cancel",
+    wrap = "RequireScope::new(Scope::BookingsWrite)"
+)]
+pub async fn cancel_bookings(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    let Info { booking_id } = info.into_inner();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let (booking, host_id) = lock_booking(&mut transaction, booking_id)
+        .await?
+        .ok_or(BookingTransitionError::NotFound(booking_id))?;
+    if !user.can_manage_host(host_id) {
+        return Err(BookingTransitionError::Forbidden(booking_id));
+    }
+    let cancelled = cancel_booking(&mut transaction, booking).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to cancel a booking.")?;
+
+    let data = ResponseData {
+        data: cancelled,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully cancelled booking {}", booking_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Apply a transition to the stored booking and persist its new status.
+///
+/// The transition hands the booking back when it is not legal from the
+/// current status, which is a 409.
+async fn transition_booking(
+    booking_id: Uuid,
+    pool: &PgPool,
+    user: &AuthenticatedUser,
+    action: &'static str,
+    transition: impl FnOnce(BookingLifecycle) -> Result<BookingLifecycle, BookingLifecycle>,
+) -> Result<HttpResponse, BookingTransitionError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let (booking, host_id) = lock_booking(&mut transaction, booking_id)
+        .await?
+        .ok_or(BookingTransitionError::NotFound(booking_id))?;
+    if !user.can_manage_host(host_id) {
+        return Err(BookingTransitionError::Forbidden(booking_id));
+    }
+    let booking = transition(booking).map_err(|booking| {
+        BookingTransitionError::Conflict(booking_id, booking.status().as_ref().to_string(), action)
+    })?;
+    let status = booking.status();
+    sqlx::query!(
+        r#"UPDATE bookings SET status = $1 WHERE id = $2"#,
+        status.as_ref(),
+        booking.id(),
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to update the status of the booking in the database.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update the status of a booking.")?;
+
+    let data = ResponseData {
+        data: BookingStatusData {
+            id: booking.id(),
+            status,
+        },
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully {} booking {}", action, booking_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+// Lock the booking so that concurrent transitions are applied one after the other
+#[tracing::instrument(name = "Lock booking in database.", skip(transaction))]
+async fn lock_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    booking_id: Uuid,
+) -> Result<Option<(BookingLifecycle, Uuid)>, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT b.status, r.host_id
+        FROM bookings b
+        JOIN rooms r ON r.id = b.room_id
+        WHERE b.id = $1
+        FOR UPDATE OF b
+        "#,
+        booking_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to lock booking in the database.")?;
+
+    row.map(|row| {
+        let status = BookingStatus::parse(&row.status).map_err(|e| anyhow::anyhow!(e))?;
+        Ok((BookingLifecycle::restore(booking_id, status), row.host_id))
+    })
+    .transpose()
+}
@@ -1,7 +1,11 @@
+mod delete;
 mod get;
 mod list;
+mod patch;
 mod post;
 
+pub use delete::*;
 pub use get::*;
 pub use list::*;
+pub use patch::*;
 pub use post::*;
@@ -0,0 +1,153 @@
+use actix_web::{delete, http::header::ContentType, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    host_id: Uuid,
+}
+
+#[derive(thiserror::Error)]
+pub enum DeleteHostError {
+    #[error("Host {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Host {0} still has upcoming bookings")]
+    Conflict(Uuid),
+    #[error("Only admins can delete hosts")]
+    Forbidden,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for DeleteHostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for DeleteHostError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteHostError::NotFound(_) => StatusCode::NOT_FOUND,
+            DeleteHostError::Conflict(_) => StatusCode::CONFLICT,
+            DeleteHostError::Forbidden => StatusCode::FORBIDDEN,
+            DeleteHostError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete a host"
+    skip(info, pool, user),
+    fields(host_id=%info.host_id, user_id=%user.user_id)
+)]
+#[delete("/hosts/{host_id}", wrap = "RequireScope::new(Scope::RoomsWrite)")]
+pub async fn delete_hosts(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, DeleteHostError> {
+    let Info { host_id } = info.into_inner();
+    if !user.is_admin() {
+        return Err(DeleteHostError::Forbidden);
+    }
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    if !lock_host(&mut transaction, host_id)
+        .await
+        .context("Failed to lock host in the database.")?
+    {
+        return Err(DeleteHostError::NotFound(host_id));
+    }
+    if has_upcoming_bookings(&mut transaction, host_id)
+        .await
+        .context("Failed to check upcoming bookings of host.")?
+    {
+        return Err(DeleteHostError::Conflict(host_id));
+    }
+    // The host and its rooms are kept for the history of their bookings
+    sqlx::query!(
+        "UPDATE rooms SET deleted_at = now() WHERE host_id = $1 AND deleted_at IS NULL",
+        host_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to delete rooms of host in the database.")?;
+    sqlx::query!("UPDATE hosts SET deleted_at = now() WHERE id = $1", host_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete host in the database.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to delete host.")?;
+
+    let data = ResponseData {
+        data: host_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully deleted host {}", host_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+// Lock the host so no room can be added while it is being deleted, and its
+// rooms so no booking can be added to them
+#[tracing::instrument(name = "Lock host in database.", skip(transaction))]
+async fn lock_host(
+    transaction: &mut Transaction<'_, Postgres>,
+    host_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT id FROM hosts WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        host_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    if row.is_none() {
+        return Ok(false);
+    }
+    sqlx::query!(
+        "SELECT id FROM rooms WHERE host_id = $1 AND deleted_at IS NULL FOR UPDATE",
+        host_id
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(true)
+}
+
+#[tracing::instrument(name = "Check upcoming bookings of host.", skip(transaction))]
+async fn has_upcoming_bookings(
+    transaction: &mut Transaction<'_, Postgres>,
+    host_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let has_bookings = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM bookings b
+            JOIN rooms r ON r.id = b.room_id
+            WHERE r.host_id = $1
+                AND b.status <> 'cancelled'
+                AND b.check_out > CURRENT_DATE
+        ) AS "exists!"
+        "#,
+        host_id,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(has_bookings)
+}
@@ -1,24 +1,72 @@
-use actix_web::{get, http::header::ContentType, web, HttpResponse};
+use actix_web::{get, http::header::ContentType, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use uuid::Uuid;
 
-use crate::utils::ResponseData;
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{HostRepository, HostWithRooms, RoomRepository},
+    infrastructure::{PgHostRepository, PgRoomRepository},
+    utils::{error_chain_fmt, ResponseData},
+};
 
 #[derive(serde::Deserialize)]
 pub struct Info {
-    host_id: u8,
+    host_id: Uuid,
+}
+
+#[derive(thiserror::Error)]
+pub enum GetHostError {
+    #[error("Host {0} does not exist")]
+    NotFound(Uuid),
+    #[error("You do not manage host {0}")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for GetHostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for GetHostError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetHostError::NotFound(_) => StatusCode::NOT_FOUND,
+            GetHostError::Forbidden(_) => StatusCode::FORBIDDEN,
+            GetHostError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
 
 #[tracing::instrument(
     name = "Retrieve host information"
-    skip(info),
+    skip(info, host_repo, room_repo, user),
+    fields(host_id=%info.host_id, user_id=%user.user_id)
 )]
-#[get("/hosts/{host_id}")]
-pub async fn get_hosts(info: web::Path<Info>) -> Result<HttpResponse, actix_web::Error> {
+#[get("/hosts/{host_id}", wrap = "RequireScope::new(Scope::RoomsRead)")]
+pub async fn get_hosts(
+    info: web::Path<Info>,
+    host_repo: web::Data<PgHostRepository>,
+    room_repo: web::Data<PgRoomRepository>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, GetHostError> {
     let Info { host_id } = info.into_inner();
+    if !user.can_manage_host(host_id) {
+        return Err(GetHostError::Forbidden(host_id));
+    }
+
+    let host = host_repo
+        .find_by_id(host_id)
+        .await?
+        .ok_or(GetHostError::NotFound(host_id))?;
+    let rooms = room_repo.find_all(host_id).await?;
 
     let response = ResponseData {
-        data: format!("Accessing host id : {}", host_id),
-        code: 200,
-        message: format!("Successfully retrieving data"),
+        data: HostWithRooms { host, rooms },
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully retrieving data".to_string(),
     };
 
     Ok(HttpResponse::Ok()
@@ -1,27 +1,90 @@
-use actix_web::{get, web, HttpResponse};
-use sqlx::PgPool;
+use actix_web::{get, http::header::ContentType, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
 
 use crate::{
-    domain::{Host, HostCategory, Room},
-    infrastructure::RoomRepositoryImpl,
-    services::get_all_rooms_for_hotel,
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{HostCategory, HostRepository, Pagination},
+    infrastructure::PgHostRepository,
+    utils::{error_chain_fmt, ResponseData},
 };
 
-#[tracing::instrument(name = "Get list of hosts")]
-#[get("/hosts")]
-pub async fn list_hosts(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
-    match get_rooms(&pool).await {
-        Ok(_rooms) => Ok(HttpResponse::Ok().finish()),
-        Err(_) => Ok(HttpResponse::Ok().finish()),
+const DEFAULT_PER_PAGE: u32 = 20;
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    DEFAULT_PER_PAGE
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct QueryData {
+    category: Option<String>,
+    #[serde(default = "default_page")]
+    page: u32,
+    #[serde(default = "default_per_page")]
+    per_page: u32,
+}
+
+#[derive(thiserror::Error)]
+pub enum ListHostsError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ListHostsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
     }
 }
 
-// #[tracing::instrument(name = "Get subscriber_id from token", skip())]
-#[tracing::instrument(name = "Query all rooms in database")]
-pub async fn get_rooms(pool: &PgPool) -> Result<Vec<Room>, String> {
-    let hotel_id = 1;
-    let room_repo = RoomRepositoryImpl::new();
-    let rooms = get_all_rooms_for_hotel(hotel_id, room_repo).await?.unwrap();
+impl ResponseError for ListHostsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ListHostsError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ListHostsError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Host managers only see the hosts they manage
+#[tracing::instrument(
+    name = "Get list of hosts",
+    skip(host_repo, user),
+    fields(user_id = %user.user_id)
+)]
+#[get("/hosts", wrap = "RequireScope::new(Scope::RoomsRead)")]
+pub async fn list_hosts(
+    query: web::Query<QueryData>,
+    host_repo: web::Data<PgHostRepository>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, ListHostsError> {
+    let QueryData {
+        category,
+        page,
+        per_page,
+    } = query.into_inner();
+    let category = category
+        .as_deref()
+        .map(HostCategory::parse)
+        .transpose()
+        .map_err(ListHostsError::ValidationError)?;
+    let pagination = Pagination::parse(page, per_page).map_err(ListHostsError::ValidationError)?;
+
+    let hosts = host_repo
+        .find_all(category.as_ref(), user.managed_host_ids(), &pagination)
+        .await?;
+
+    let response = ResponseData {
+        data: hosts,
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully retrieving data".to_string(),
+    };
 
-    Ok(rooms)
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(response))
 }
@@ -0,0 +1,133 @@
+use actix_web::{http::header::ContentType, patch, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{GeneralName, HostCategory, HostChanges},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    host_id: Uuid,
+}
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    name: Option<String>,
+    category: Option<String>,
+}
+
+impl TryFrom<BodyData> for HostChanges {
+    type Error = String;
+
+    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let BodyData { name, category } = value;
+        if name.is_none() && category.is_none() {
+            return Err("There is nothing to update".to_string());
+        }
+        let name = name.map(GeneralName::parse).transpose()?;
+        let category = category.as_deref().map(HostCategory::parse).transpose()?;
+
+        Ok(HostChanges { name, category })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum PatchHostError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Host {0} does not exist")]
+    NotFound(Uuid),
+    #[error("You do not manage host {0}")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PatchHostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PatchHostError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PatchHostError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PatchHostError::NotFound(_) => StatusCode::NOT_FOUND,
+            PatchHostError::Forbidden(_) => StatusCode::FORBIDDEN,
+            PatchHostError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Update a host"
+    skip(info, body, pool, user),
+    fields(host_id=%info.host_id, user_id=%user.user_id)
+)]
+#[patch("/hosts/{host_id}", wrap = "RequireScope::new(Scope::RoomsWrite)")]
+pub async fn update_hosts(
+    info: web::Path<Info>,
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, PatchHostError> {
+    let Info { host_id } = info.into_inner();
+    if !user.can_manage_host(host_id) {
+        return Err(PatchHostError::Forbidden(host_id));
+    }
+    let changes: HostChanges = body.0.try_into().map_err(PatchHostError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let updated = update_host(&mut transaction, host_id, &changes)
+        .await
+        .context("Failed to update host in the database.")?;
+    if !updated {
+        return Err(PatchHostError::NotFound(host_id));
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update host.")?;
+
+    let data = ResponseData {
+        data: host_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully updated host {}", host_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+#[tracing::instrument(name = "Saving host changes in database.", skip(transaction, changes))]
+pub async fn update_host(
+    transaction: &mut Transaction<'_, Postgres>,
+    host_id: Uuid,
+    changes: &HostChanges,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE hosts
+        SET name = COALESCE($2, name),
+            category = COALESCE($3, category)
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+        host_id,
+        changes.name.as_ref().map(|name| name.as_ref()),
+        changes.category.as_ref().map(|category| category.as_ref()),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
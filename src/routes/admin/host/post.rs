@@ -5,6 +5,7 @@ use sqlx::{Executor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
     domain::{GeneralName, HostCategory, NewHost},
     utils::{error_chain_fmt, ResponseData},
 };
@@ -30,6 +31,8 @@ impl TryFrom<BodyData> for NewHost {
 pub enum PostHostError {
     #[error("{0}")]
     ValidationError(String),
+    #[error("Only admins can add hosts")]
+    Forbidden,
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -44,6 +47,7 @@ impl ResponseError for PostHostError {
     fn status_code(&self) -> StatusCode {
         match self {
             PostHostError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PostHostError::Forbidden => StatusCode::FORBIDDEN,
             PostHostError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -51,13 +55,18 @@ impl ResponseError for PostHostError {
 
 #[tracing::instrument(
     name = "Add a new room"
-    skip(pool, body),
+    skip(pool, body, user),
+    fields(user_id=%user.user_id)
 )]
-#[post("/hosts")]
+#[post("/hosts", wrap = "RequireScope::new(Scope::RoomsWrite)")]
 pub async fn add_hosts(
     body: web::Json<BodyData>,
     pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
 ) -> Result<HttpResponse, PostHostError> {
+    if !user.is_admin() {
+        return Err(PostHostError::Forbidden);
+    }
     let new_host: NewHost = body.0.try_into().map_err(PostHostError::ValidationError)?;
     let mut transaction = pool
         .begin()
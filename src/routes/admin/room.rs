@@ -1,7 +1,9 @@
-mod get;
+mod delete;
 mod list;
+mod patch;
 mod post;
 
-pub use get::*;
+pub use delete::*;
 pub use list::*;
+pub use patch::*;
 pub use post::*;
@@ -0,0 +1,134 @@
+use actix_web::{delete, http::header::ContentType, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    room_id: Uuid,
+}
+
+#[derive(thiserror::Error)]
+pub enum DeleteRoomError {
+    #[error("Room {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Room {0} still has upcoming bookings")]
+    Conflict(Uuid),
+    #[error("Room {0} belongs to a host you do not manage")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for DeleteRoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for DeleteRoomError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteRoomError::NotFound(_) => StatusCode::NOT_FOUND,
+            DeleteRoomError::Conflict(_) => StatusCode::CONFLICT,
+            DeleteRoomError::Forbidden(_) => StatusCode::FORBIDDEN,
+            DeleteRoomError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Delete a room"
+    skip(info, pool, user),
+    fields(room_id=%info.room_id, user_id=%user.user_id)
+)]
+#[delete("/rooms/{room_id}", wrap = "RequireScope::new(Scope::RoomsWrite)")]
+pub async fn delete_rooms(
+    info: web::Path<Info>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, DeleteRoomError> {
+    let Info { room_id } = info.into_inner();
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let host_id = lock_room(&mut transaction, room_id)
+        .await
+        .context("Failed to lock room in the database.")?
+        .ok_or(DeleteRoomError::NotFound(room_id))?;
+    if !user.can_manage_host(host_id) {
+        return Err(DeleteRoomError::Forbidden(room_id));
+    }
+    if has_upcoming_bookings(&mut transaction, room_id)
+        .await
+        .context("Failed to check upcoming bookings of room.")?
+    {
+        return Err(DeleteRoomError::Conflict(room_id));
+    }
+    // The room is kept for the history of its bookings
+    sqlx::query!("UPDATE rooms SET deleted_at = now() WHERE id = $1", room_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete room in the database.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to delete room.")?;
+
+    let data = ResponseData {
+        data: room_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully deleted room {}", room_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+// Lock the room so no booking can be added while it is being deleted,
+// returns the host of the room if it exists
+#[tracing::instrument(name = "Lock room in database.", skip(transaction))]
+async fn lock_room(
+    transaction: &mut Transaction<'_, Postgres>,
+    room_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let host_id = sqlx::query_scalar!(
+        "SELECT host_id FROM rooms WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        room_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(host_id)
+}
+
+#[tracing::instrument(name = "Check upcoming bookings of room.", skip(transaction))]
+async fn has_upcoming_bookings(
+    transaction: &mut Transaction<'_, Postgres>,
+    room_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let has_bookings = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM bookings
+            WHERE room_id = $1
+                AND status <> 'cancelled'
+                AND check_out > CURRENT_DATE
+        ) AS "exists!"
+        "#,
+        room_id,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(has_bookings)
+}
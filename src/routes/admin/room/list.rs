@@ -1,46 +1,65 @@
-use actix_web::{get, http::header::ContentType, web, HttpResponse};
-use sqlx::PgPool;
+use actix_web::{get, http::header::ContentType, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
 use uuid::Uuid;
 
 use crate::{
-    domain::{GeneralName, Host, HostCategory, Room},
-    infrastructure::RoomRepositoryImpl,
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    infrastructure::PgRoomRepository,
     services::get_all_rooms_for_hotel,
-    utils::ResponseData,
+    utils::{error_chain_fmt, ResponseData},
 };
 
-#[tracing::instrument(name = "Get list of rooms")]
-#[get("/rooms")]
-pub async fn list_rooms(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
-    let host = Host {
-        id: Uuid::new_v4(),
-        name: GeneralName::parse("Intercontinental".into()).unwrap(),
-        category: HostCategory::parse("hotel").unwrap(),
-    };
-    let rooms = vec![Room {
-        id: Uuid::new_v4(),
-        name: GeneralName::parse("Standard 2-bed rooms".into()).unwrap(),
-        container: host,
-        description: "Two beds room".into(),
-        number_of_beds: 2,
-    }];
+#[derive(serde::Deserialize, Debug)]
+pub struct QueryData {
+    host_id: Uuid,
+}
+
+#[derive(thiserror::Error)]
+pub enum ListRoomsError {
+    #[error("You do not manage host {0}")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ListRoomsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ListRoomsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ListRoomsError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ListRoomsError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Get list of rooms",
+    skip(room_repo, user),
+    fields(user_id = %user.user_id)
+)]
+#[get("/rooms", wrap = "RequireScope::new(Scope::RoomsRead)")]
+pub async fn list_rooms(
+    query: web::Query<QueryData>,
+    room_repo: web::Data<PgRoomRepository>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, ListRoomsError> {
+    if !user.can_manage_host(query.host_id) {
+        return Err(ListRoomsError::Forbidden(query.host_id));
+    }
+    let rooms = get_all_rooms_for_hotel(query.host_id, room_repo.get_ref()).await?;
 
     let response = ResponseData {
         data: rooms,
-        code: 200,
-        message: format!("Successfully retrieving data"),
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully retrieving data".to_string(),
     };
 
     Ok(HttpResponse::Ok()
         .content_type(ContentType::json())
         .json(response))
 }
-
-#[tracing::instrument(name = "Query all rooms in database")]
-pub async fn get_rooms(pool: &PgPool) -> Result<Vec<Room>, String> {
-    let hotel_id = 1;
-    let room_repo = RoomRepositoryImpl::new();
-    let rooms = get_all_rooms_for_hotel(hotel_id, room_repo).await?.unwrap();
-
-    Ok(rooms)
-}
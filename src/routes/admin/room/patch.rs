@@ -0,0 +1,195 @@
+use actix_web::{http::header::ContentType, patch, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{
+        parse_number_of_beds, parse_price_per_night, CancellationPolicy, GeneralName, RoomChanges,
+    },
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    room_id: Uuid,
+}
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    name: Option<String>,
+    description: Option<String>,
+    number_of_beds: Option<u16>,
+    price_per_night: Option<i64>,
+    cancellation_policy: Option<CancellationPolicy>,
+}
+
+impl TryFrom<BodyData> for RoomChanges {
+    type Error = String;
+
+    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let BodyData {
+            name,
+            description,
+            number_of_beds,
+            price_per_night,
+            cancellation_policy,
+        } = value;
+        if name.is_none()
+            && description.is_none()
+            && number_of_beds.is_none()
+            && price_per_night.is_none()
+            && cancellation_policy.is_none()
+        {
+            return Err("There is nothing to update".to_string());
+        }
+        let name = name.map(GeneralName::parse).transpose()?;
+        let number_of_beds = number_of_beds.map(parse_number_of_beds).transpose()?;
+        let price_per_night = price_per_night.map(parse_price_per_night).transpose()?;
+        let cancellation_policy = cancellation_policy
+            .map(CancellationPolicy::parse)
+            .transpose()?;
+
+        Ok(RoomChanges {
+            name,
+            description,
+            number_of_beds,
+            price_per_night,
+            cancellation_policy,
+        })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum PatchRoomError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Room {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Room {0} belongs to a host you do not manage")]
+    Forbidden(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PatchRoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PatchRoomError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PatchRoomError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PatchRoomError::NotFound(_) => StatusCode::NOT_FOUND,
+            PatchRoomError::Forbidden(_) => StatusCode::FORBIDDEN,
+            PatchRoomError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Update a room"
+    skip(info, body, pool, user),
+    fields(room_id=%info.room_id, user_id=%user.user_id)
+)]
+#[patch("/rooms/{room_id}", wrap = "RequireScope::new(Scope::RoomsWrite)")]
+pub async fn update_rooms(
+    info: web::Path<Info>,
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, PatchRoomError> {
+    let Info { room_id } = info.into_inner();
+    let changes: RoomChanges = body.0.try_into().map_err(PatchRoomError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let host_id = get_room_host(&mut transaction, room_id)
+        .await
+        .context("Failed to retrieve the host of the room.")?
+        .ok_or(PatchRoomError::NotFound(room_id))?;
+    if !user.can_manage_host(host_id) {
+        return Err(PatchRoomError::Forbidden(room_id));
+    }
+    let updated = update_room(&mut transaction, room_id, &changes)
+        .await
+        .context("Failed to update room in the database.")?;
+    if !updated {
+        return Err(PatchRoomError::NotFound(room_id));
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update room.")?;
+
+    let data = ResponseData {
+        data: room_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully updated room {}", room_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+#[tracing::instrument(name = "Get host of room in database.", skip(transaction))]
+async fn get_room_host(
+    transaction: &mut Transaction<'_, Postgres>,
+    room_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let host_id = sqlx::query_scalar!(
+        "SELECT host_id FROM rooms WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        room_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(host_id)
+}
+
+#[tracing::instrument(name = "Saving room changes in database.", skip(transaction, changes))]
+pub async fn update_room(
+    transaction: &mut Transaction<'_, Postgres>,
+    room_id: Uuid,
+    changes: &RoomChanges,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE rooms
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            number_of_beds = COALESCE($4, number_of_beds),
+            price_per_night = COALESCE($5, price_per_night),
+            cancellation_policy = COALESCE($6, cancellation_policy),
+            free_cancellation_days = COALESCE($7, free_cancellation_days),
+            cancellation_penalty_percent = COALESCE($8, cancellation_penalty_percent)
+        WHERE id = $1
+        "#,
+        room_id,
+        changes.name.as_ref().map(|name| name.as_ref()),
+        changes.description,
+        changes.number_of_beds.map(|beds| beds as i16),
+        changes.price_per_night,
+        changes
+            .cancellation_policy
+            .as_ref()
+            .map(|policy| policy.as_ref()),
+        changes
+            .cancellation_policy
+            .map(|policy| policy.free_until_days()),
+        changes
+            .cancellation_policy
+            .map(|policy| policy.penalty_percent()),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
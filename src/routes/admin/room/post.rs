@@ -1,11 +1,14 @@
 use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
 use anyhow::Context;
 use reqwest::StatusCode;
-use sqlx::{Executor, PgPool, Postgres, Transaction};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 use crate::{
-    domain::{GeneralName, NewRoom},
+    authentication::{AuthenticatedUser, RequireScope, Scope},
+    domain::{
+        parse_number_of_beds, parse_price_per_night, CancellationPolicy, GeneralName, NewRoom,
+    },
     utils::{error_chain_fmt, ResponseData},
 };
 
@@ -15,6 +18,10 @@ pub struct BodyData {
     host_id: Uuid,
     description: String,
     number_of_beds: u16,
+    #[serde(default)]
+    price_per_night: i64,
+    #[serde(default)]
+    cancellation_policy: CancellationPolicy,
 }
 
 impl TryFrom<BodyData> for NewRoom {
@@ -26,14 +33,21 @@ impl TryFrom<BodyData> for NewRoom {
             host_id,
             description,
             number_of_beds,
+            price_per_night,
+            cancellation_policy,
         } = value;
         let name = GeneralName::parse(name)?;
+        let number_of_beds = parse_number_of_beds(number_of_beds)?;
+        let price_per_night = parse_price_per_night(price_per_night)?;
+        let cancellation_policy = CancellationPolicy::parse(cancellation_policy)?;
 
         Ok(NewRoom {
             name,
             host_id,
             description,
             number_of_beds,
+            price_per_night,
+            cancellation_policy,
         })
     }
 }
@@ -42,6 +56,8 @@ impl TryFrom<BodyData> for NewRoom {
 pub enum PostRoomError {
     #[error("{0}")]
     ValidationError(String),
+    #[error("You do not manage host {0}")]
+    Forbidden(Uuid),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -56,6 +72,7 @@ impl ResponseError for PostRoomError {
     fn status_code(&self) -> StatusCode {
         match self {
             PostRoomError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PostRoomError::Forbidden(_) => StatusCode::FORBIDDEN,
             PostRoomError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -63,20 +80,25 @@ impl ResponseError for PostRoomError {
 
 #[tracing::instrument(
     name = "Add a new room"
-    skip(pool, body),
+    skip(pool, body, user),
+    fields(user_id=%user.user_id)
 )]
-#[post("/rooms")]
+#[post("/rooms", wrap = "RequireScope::new(Scope::RoomsWrite)")]
 pub async fn add_rooms(
     body: web::Json<BodyData>,
     pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
 ) -> Result<HttpResponse, PostRoomError> {
     let new_room: NewRoom = body.0.try_into().map_err(PostRoomError::ValidationError)?;
+    if !user.can_manage_host(new_room.host_id) {
+        return Err(PostRoomError::Forbidden(new_room.host_id));
+    }
 
     let mut transaction = pool
         .begin()
         .await
         .context("Failed to acquire a Postgres connection from pool")?;
-    let room_id = insert_room(&mut transaction, &new_room)
+    let room_id = insert_room(&mut transaction, &new_room, user.user_id)
         .await
         .context("Failed to insert new room in the database.")?;
     transaction
@@ -95,6 +117,8 @@ pub async fn add_rooms(
         .json(data))
 }
 
+/// Store the room, the host is locked so it cannot be deleted meanwhile.
+/// Fails when there is no such host.
 #[tracing::instrument(
     name = "Saving new room details in database.",
     skip(transaction, new_room)
@@ -102,20 +126,33 @@ pub async fn add_rooms(
 pub async fn insert_room(
     transaction: &mut Transaction<'_, Postgres>,
     new_room: &NewRoom,
+    created_by: Uuid,
 ) -> Result<Uuid, sqlx::Error> {
-    let room_id = Uuid::new_v4();
-    let query = sqlx::query!(
+    let room_id = sqlx::query_scalar!(
         r#"
-        INSERT INTO rooms (id, host_id, name, description, number_of_beds)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO rooms (
+            id, host_id, name, description, number_of_beds, created_by, price_per_night,
+            cancellation_policy, free_cancellation_days, cancellation_penalty_percent
+        )
+        SELECT $1, h.id, $3, $4, $5, $6, $7, $8, $9, $10
+        FROM hosts h
+        WHERE h.id = $2 AND h.deleted_at IS NULL
+        FOR SHARE
+        RETURNING id
         "#,
-        room_id,
+        Uuid::new_v4(),
         new_room.host_id,
         new_room.name.as_ref(),
         new_room.description,
         new_room.number_of_beds as i16,
-    );
-    transaction.execute(query).await?;
+        created_by,
+        new_room.price_per_night,
+        new_room.cancellation_policy.as_ref(),
+        new_room.cancellation_policy.free_until_days(),
+        new_room.cancellation_policy.penalty_percent(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
 
     Ok(room_id)
 }
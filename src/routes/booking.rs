@@ -0,0 +1,7 @@
+mod cancel;
+mod patch;
+mod post;
+
+pub use cancel::*;
+pub use patch::*;
+pub use post::*;
@@ -0,0 +1,205 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        BookingLifecycle, BookingStatus, CancellationCharges, CancellationPolicy, CustomerEmail,
+    },
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    booking_id: Uuid,
+}
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    // Bookings are made without an account, the email they were made with
+    // proves the guest owns the booking
+    guest_email: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CancelledBooking {
+    pub id: Uuid,
+    pub status: BookingStatus,
+    #[serde(flatten)]
+    pub charges: CancellationCharges,
+}
+
+#[derive(thiserror::Error)]
+pub enum CancelBookingError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Booking {0} does not exist")]
+    NotFound(Uuid),
+    #[error("Booking {0} is {1}, it cannot be cancelled")]
+    Conflict(Uuid, String),
+    #[error("Booking {0} starts on {1}, it can no longer be cancelled")]
+    TooLate(Uuid, NaiveDate),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CancelBookingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for CancelBookingError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CancelBookingError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CancelBookingError::NotFound(_) => StatusCode::NOT_FOUND,
+            CancelBookingError::Conflict(..) => StatusCode::CONFLICT,
+            CancelBookingError::TooLate(..) => StatusCode::CONFLICT,
+            CancelBookingError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Cancel a pending or confirmed booking under the cancellation policy it
+/// was made with, the penalty and the refund are recorded along with it
+#[tracing::instrument(
+    name = "Cancel a booking"
+    skip(info, body, pool),
+    fields(booking_id=%info.booking_id)
+)]
+#[post("/bookings/{booking_id}/cancel")]
+pub async fn guest_cancel_bookings(
+    info: web::Path<Info>,
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, CancelBookingError> {
+    let Info { booking_id } = info.into_inner();
+    let guest_email =
+        CustomerEmail::parse(body.0.guest_email).map_err(CancelBookingError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    // A wrong email is a 404 so that it does not tell which bookings exist
+    let stored_status = sqlx::query_scalar!(
+        r#"
+        SELECT status
+        FROM bookings
+        WHERE id = $1 AND guest_email = $2
+        FOR UPDATE
+        "#,
+        booking_id,
+        guest_email.as_ref(),
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to lock booking in the database.")?
+    .ok_or(CancelBookingError::NotFound(booking_id))?;
+    let status = BookingStatus::parse(&stored_status).map_err(|e| anyhow::anyhow!(e))?;
+    let cancelled = cancel_booking(
+        &mut transaction,
+        BookingLifecycle::restore(booking_id, status),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to cancel a booking.")?;
+
+    let data = ResponseData {
+        data: cancelled,
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully cancelled booking {}", booking_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Cancel a booking locked by the caller and record the charges of its
+/// cancellation policy, whoever cancels it.
+///
+/// Stays starting today or earlier can no longer be cancelled, they end with
+/// a check-out or a no-show instead.
+#[tracing::instrument(name = "Cancel booking in database.", skip(transaction, booking))]
+pub async fn cancel_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    booking: BookingLifecycle,
+) -> Result<CancelledBooking, CancelBookingError> {
+    let booking_id = booking.id();
+    let booking = match booking {
+        BookingLifecycle::Pending(booking) => booking.cancel(),
+        BookingLifecycle::Confirmed(booking) => booking.cancel(),
+        booking => {
+            return Err(CancelBookingError::Conflict(
+                booking_id,
+                booking.status().as_ref().to_string(),
+            ))
+        }
+    };
+    let stored_booking = sqlx::query!(
+        r#"
+        SELECT check_in, total_price, cancellation_policy, free_cancellation_days,
+            cancellation_penalty_percent
+        FROM bookings
+        WHERE id = $1
+        "#,
+        booking_id,
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to retrieve the cancellation policy of the booking.")?;
+    let cancelled_at = Utc::now();
+    if stored_booking.check_in <= cancelled_at.date_naive() {
+        return Err(CancelBookingError::TooLate(
+            booking_id,
+            stored_booking.check_in,
+        ));
+    }
+    let policy = CancellationPolicy::from_columns(
+        &stored_booking.cancellation_policy,
+        stored_booking.free_cancellation_days,
+        stored_booking.cancellation_penalty_percent,
+    )
+    .map_err(|e| anyhow::anyhow!(e))?;
+    let charges = policy.evaluate(
+        stored_booking.total_price,
+        stored_booking.check_in,
+        cancelled_at.date_naive(),
+    );
+
+    let status = booking.status();
+    sqlx::query!(
+        r#"UPDATE bookings SET status = $1 WHERE id = $2"#,
+        status.as_ref(),
+        booking_id,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to update the status of the booking in the database.")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO booking_cancellations (booking_id, penalty, refund, cancelled_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        booking_id,
+        charges.penalty,
+        charges.refund,
+        cancelled_at,
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to record the cancellation of the booking.")?;
+
+    Ok(CancelledBooking {
+        id: booking_id,
+        status,
+        charges,
+    })
+}
@@ -0,0 +1,288 @@
+use actix_web::{http::header::ContentType, patch, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    domain::{BookingChanges, BookingLifecycle, BookingStatus, CustomerEmail, StayRange},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+use super::is_overlapping_stay;
+
+#[derive(serde::Deserialize)]
+pub struct Info {
+    booking_id: Uuid,
+}
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    // Bookings are made without an account, the email they were made with
+    // proves the guest owns the booking
+    guest_email: String,
+    room_id: Option<Uuid>,
+    check_in: Option<NaiveDate>,
+    check_out: Option<NaiveDate>,
+}
+
+impl TryFrom<BodyData> for BookingChanges {
+    type Error = String;
+
+    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let BodyData {
+            guest_email,
+            room_id,
+            check_in,
+            check_out,
+        } = value;
+        if room_id.is_none() && check_in.is_none() && check_out.is_none() {
+            return Err("There is nothing to update".to_string());
+        }
+        let guest_email = CustomerEmail::parse(guest_email)?;
+
+        Ok(BookingChanges {
+            guest_email,
+            room_id,
+            check_in,
+            check_out,
+        })
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ModifiedBooking {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub status: BookingStatus,
+    pub total_price: i64,
+}
+
+#[derive(thiserror::Error)]
+pub enum PatchBookingError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Booking {0} does not exist")]
+    BookingNotFound(Uuid),
+    #[error("Room {0} does not exist")]
+    RoomNotFound(Uuid),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PatchBookingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PatchBookingError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PatchBookingError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PatchBookingError::BookingNotFound(_) => StatusCode::NOT_FOUND,
+            PatchBookingError::RoomNotFound(_) => StatusCode::NOT_FOUND,
+            PatchBookingError::Conflict(_) => StatusCode::CONFLICT,
+            PatchBookingError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Move a pending or confirmed booking to other dates or another room.
+///
+/// The stay is re-priced at the current price of the room, and a booking moved
+/// to another room takes its cancellation policy. When the new nights are taken
+/// the booking is left as it was.
+#[tracing::instrument(
+    name = "Update a booking"
+    skip(info, body, pool),
+    fields(booking_id=%info.booking_id)
+)]
+#[patch("/bookings/{booking_id}")]
+pub async fn update_bookings(
+    info: web::Path<Info>,
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, PatchBookingError> {
+    let Info { booking_id } = info.into_inner();
+    let changes: BookingChanges = body
+        .0
+        .try_into()
+        .map_err(PatchBookingError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    // A wrong email is a 404 so that it does not tell which bookings exist
+    let stored_booking = sqlx::query!(
+        r#"
+        SELECT room_id, check_in, check_out, total_price, status
+        FROM bookings
+        WHERE id = $1 AND guest_email = $2
+        FOR UPDATE
+        "#,
+        booking_id,
+        changes.guest_email.as_ref(),
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to lock booking in the database.")?
+    .ok_or(PatchBookingError::BookingNotFound(booking_id))?;
+    let status = BookingStatus::parse(&stored_booking.status).map_err(|e| anyhow::anyhow!(e))?;
+    match BookingLifecycle::restore(booking_id, status) {
+        BookingLifecycle::Pending(_) | BookingLifecycle::Confirmed(_) => {}
+        booking => {
+            return Err(PatchBookingError::Conflict(format!(
+                "Booking {} is {}, it cannot be modified",
+                booking_id,
+                booking.status().as_ref()
+            )))
+        }
+    }
+    let room_id = changes.room_id.unwrap_or(stored_booking.room_id);
+    let stay = StayRange::parse(
+        changes.check_in.unwrap_or(stored_booking.check_in),
+        changes.check_out.unwrap_or(stored_booking.check_out),
+    )
+    .map_err(PatchBookingError::ValidationError)?;
+    if room_id == stored_booking.room_id
+        && stay.check_in() == stored_booking.check_in
+        && stay.check_out() == stored_booking.check_out
+    {
+        // Nothing to re-price or to keep in the history
+        let data = ResponseData {
+            data: ModifiedBooking {
+                id: booking_id,
+                room_id,
+                check_in: stay.check_in(),
+                check_out: stay.check_out(),
+                status,
+                total_price: stored_booking.total_price,
+            },
+            code: StatusCode::OK.as_u16(),
+            message: format!("Booking {} is unchanged", booking_id),
+        };
+
+        return Ok(HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .json(data));
+    }
+
+    let total_price = update_booking(&mut transaction, booking_id, room_id, &stay)
+        .await
+        .map_err(|e| {
+            if is_overlapping_stay(&e) {
+                PatchBookingError::Conflict(format!(
+                    "Room {} is already booked between {} and {}",
+                    room_id,
+                    stay.check_in(),
+                    stay.check_out()
+                ))
+            } else {
+                PatchBookingError::UnexpectedError(
+                    anyhow::Error::new(e).context("Failed to update booking in the database."),
+                )
+            }
+        })?
+        .ok_or(PatchBookingError::RoomNotFound(room_id))?;
+    sqlx::query!(
+        r#"
+        INSERT INTO booking_modifications (
+            id, booking_id, previous_room_id, previous_check_in, previous_check_out,
+            previous_total_price, room_id, check_in, check_out, total_price, modified_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+        Uuid::new_v4(),
+        booking_id,
+        stored_booking.room_id,
+        stored_booking.check_in,
+        stored_booking.check_out,
+        stored_booking.total_price,
+        room_id,
+        stay.check_in(),
+        stay.check_out(),
+        total_price,
+        Utc::now(),
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to record the modification of the booking.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update booking.")?;
+
+    let data = ResponseData {
+        data: ModifiedBooking {
+            id: booking_id,
+            room_id,
+            check_in: stay.check_in(),
+            check_out: stay.check_out(),
+            status,
+            total_price,
+        },
+        code: StatusCode::OK.as_u16(),
+        message: format!("Successfully updated booking {}", booking_id),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Move the booking to the room and the stay in a single statement, so its
+/// old nights are released as the new ones are taken. The policy of the room
+/// is only copied when the room changes. Returns the new total price, or
+/// `None` when there is no such room. The room is locked so it cannot be
+/// deleted meanwhile.
+#[tracing::instrument(name = "Saving booking changes in database.", skip(transaction, stay))]
+pub async fn update_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    booking_id: Uuid,
+    room_id: Uuid,
+    stay: &StayRange,
+) -> Result<Option<i64>, sqlx::Error> {
+    let room = sqlx::query_scalar!(
+        "SELECT id FROM rooms WHERE id = $1 AND deleted_at IS NULL FOR SHARE",
+        room_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    if room.is_none() {
+        return Ok(None);
+    }
+    let total_price = sqlx::query_scalar!(
+        r#"
+        UPDATE bookings b
+        SET room_id = r.id,
+            check_in = $3,
+            check_out = $4,
+            total_price = r.price_per_night * $5,
+            cancellation_policy = CASE WHEN b.room_id = r.id
+                THEN b.cancellation_policy ELSE r.cancellation_policy END,
+            free_cancellation_days = CASE WHEN b.room_id = r.id
+                THEN b.free_cancellation_days ELSE r.free_cancellation_days END,
+            cancellation_penalty_percent = CASE WHEN b.room_id = r.id
+                THEN b.cancellation_penalty_percent ELSE r.cancellation_penalty_percent END
+        FROM rooms r
+        WHERE b.id = $1 AND r.id = $2
+        RETURNING b.total_price
+        "#,
+        booking_id,
+        room_id,
+        stay.check_in(),
+        stay.check_out(),
+        stay.nights(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(total_price)
+}
@@ -0,0 +1,177 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    domain::{CustomerEmail, GeneralName, NewBooking, StayRange},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+/// Exclusion constraint preventing overlapping stays for the same room
+const NO_OVERLAPPING_STAYS_CONSTRAINT: &str = "bookings_no_overlapping_stays";
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    room_id: Uuid,
+    guest_name: String,
+    guest_email: String,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+}
+
+impl TryFrom<BodyData> for NewBooking {
+    type Error = String;
+
+    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let BodyData {
+            room_id,
+            guest_name,
+            guest_email,
+            check_in,
+            check_out,
+        } = value;
+        let guest_name = GeneralName::parse(guest_name)?;
+        let guest_email = CustomerEmail::parse(guest_email)?;
+        let stay = StayRange::parse(check_in, check_out)?;
+
+        Ok(NewBooking {
+            room_id,
+            guest_name,
+            guest_email,
+            stay,
+        })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum PostBookingError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Room {0} does not exist")]
+    NotFound(Uuid),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PostBookingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PostBookingError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PostBookingError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PostBookingError::NotFound(_) => StatusCode::NOT_FOUND,
+            PostBookingError::Conflict(_) => StatusCode::CONFLICT,
+            PostBookingError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Add a new booking"
+    skip(pool, body),
+)]
+#[post("/bookings")]
+pub async fn add_bookings(
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, PostBookingError> {
+    let new_booking: NewBooking = body
+        .0
+        .try_into()
+        .map_err(PostBookingError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from pool")?;
+    let booking_id = insert_booking(&mut transaction, &new_booking)
+        .await
+        .map_err(|e| {
+            if is_overlapping_stay(&e) {
+                PostBookingError::Conflict(format!(
+                    "Room {} is already booked between {} and {}",
+                    new_booking.room_id,
+                    new_booking.stay.check_in(),
+                    new_booking.stay.check_out()
+                ))
+            } else {
+                PostBookingError::UnexpectedError(
+                    anyhow::Error::new(e).context("Failed to insert new booking in the database."),
+                )
+            }
+        })?
+        .ok_or(PostBookingError::NotFound(new_booking.room_id))?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store new booking.")?;
+
+    let data = ResponseData {
+        data: booking_id,
+        code: StatusCode::OK.as_u16(),
+        message: format!(
+            "Successfully created new booking for {}",
+            new_booking.guest_name.as_ref()
+        ),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Store the booking with the price and the cancellation policy the room has
+/// now, returns `None` when there is no such room. The room is locked so it
+/// cannot be deleted meanwhile.
+#[tracing::instrument(
+    name = "Saving new booking details in database.",
+    skip(transaction, new_booking)
+)]
+pub async fn insert_booking(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_booking: &NewBooking,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let booking_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO bookings (
+            id, room_id, guest_name, guest_email, check_in, check_out, status, created_at,
+            total_price, cancellation_policy, free_cancellation_days,
+            cancellation_penalty_percent
+        )
+        SELECT $1, r.id, $3, $4, $5, $6, 'pending', $7,
+            r.price_per_night * $8, r.cancellation_policy, r.free_cancellation_days,
+            r.cancellation_penalty_percent
+        FROM rooms r
+        WHERE r.id = $2 AND r.deleted_at IS NULL
+        FOR SHARE
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        new_booking.room_id,
+        new_booking.guest_name.as_ref(),
+        new_booking.guest_email.as_ref(),
+        new_booking.stay.check_in(),
+        new_booking.stay.check_out(),
+        Utc::now(),
+        new_booking.stay.nights(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(booking_id)
+}
+
+pub fn is_overlapping_stay(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|e| e.constraint())
+        .is_some_and(|constraint| constraint == NO_OVERLAPPING_STAYS_CONSTRAINT)
+}
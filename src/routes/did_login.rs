@@ -0,0 +1,243 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::start_session;
+use crate::{
+    authentication::{
+        check_did_challenge, is_totp_enabled, issue_did_challenge, link_user_did, resolve_did_key,
+        verify_current_password, verify_did_challenge, verify_second_factor, AuthError,
+        AuthenticatedUser, DummyPasswordHash, JwtKeys, Scope,
+    },
+    configuration::PasswordHashingSettings,
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+struct ChallengeBodyData {
+    did: String,
+}
+
+#[derive(serde::Deserialize)]
+struct VerifyBodyData {
+    did: String,
+    nonce: String,
+    // Base64url encoded Ed25519 signature of the nonce
+    signature: String,
+    // Space-delimited, the session gets every scope of the role when it is omitted
+    scope: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct LinkBodyData {
+    did: String,
+    nonce: String,
+    // Base64url encoded Ed25519 signature of the nonce
+    signature: String,
+    // The current password, or a TOTP or recovery code when TOTP is enabled
+    password: Option<Secret<String>>,
+    code: Option<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum DidLoginError {
+    #[error("{0}")]
+    InvalidDid(String),
+    #[error("{0}")]
+    InvalidScope(String),
+    #[error("Authentication failed")]
+    AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for DidLoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for DidLoginError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DidLoginError::InvalidDid(_) => StatusCode::BAD_REQUEST,
+            DidLoginError::InvalidScope(_) => StatusCode::BAD_REQUEST,
+            DidLoginError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            DidLoginError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<AuthError> for DidLoginError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::InvalidCredentials(_) => DidLoginError::AuthError(e.into()),
+            AuthError::UnexpectedError(_) => DidLoginError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum LinkDidError {
+    #[error("{0}")]
+    InvalidDid(String),
+    #[error("Authentication failed")]
+    AuthError(#[source] anyhow::Error),
+    #[error("The current password or two-factor authentication code is incorrect")]
+    WrongProof(#[source] anyhow::Error),
+    #[error("The DID is linked to another account")]
+    Conflict,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for LinkDidError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for LinkDidError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            LinkDidError::InvalidDid(_) => StatusCode::BAD_REQUEST,
+            LinkDidError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            LinkDidError::WrongProof(_) => StatusCode::BAD_REQUEST,
+            LinkDidError::Conflict => StatusCode::CONFLICT,
+            LinkDidError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<AuthError> for LinkDidError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::InvalidCredentials(_) => LinkDidError::AuthError(e.into()),
+            AuthError::UnexpectedError(_) => LinkDidError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[tracing::instrument(name = "Issue a DID challenge", skip(body, pool), fields(did = %body.did))]
+#[post("/auth/did/challenge")]
+pub async fn did_challenge(
+    body: web::Json<ChallengeBodyData>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, DidLoginError> {
+    resolve_did_key(&body.did).map_err(DidLoginError::InvalidDid)?;
+    let challenge = issue_did_challenge(&body.did, &pool).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(challenge))
+}
+
+#[tracing::instrument(
+    name = "DID login",
+    skip(body, pool, jwt_keys),
+    fields(
+        did = %body.did,
+        user_id=tracing::field::Empty,
+    )
+)]
+#[post("/auth/did/verify")]
+pub async fn did_verify(
+    body: web::Json<VerifyBodyData>,
+    pool: web::Data<PgPool>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> Result<HttpResponse, DidLoginError> {
+    let requested_scopes = body
+        .scope
+        .as_deref()
+        .map(Scope::parse_list)
+        .transpose()
+        .map_err(DidLoginError::InvalidScope)?;
+    resolve_did_key(&body.did).map_err(DidLoginError::InvalidDid)?;
+
+    let user_id = verify_did_challenge(&body.did, &body.nonce, &body.signature, &pool).await?;
+    tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+    let data = start_session(user_id, requested_scopes, &pool, &jwt_keys).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Link a DID to the account of the current user, the answer to a challenge
+/// of `/auth/did/challenge` proves the user holds the key.
+///
+/// The DID logs in with every scope of the role, so the user proves again
+/// who they are with the current password, or a TOTP code when TOTP is
+/// enabled.
+#[tracing::instrument(
+    name = "Link a DID",
+    skip(body, pool, hashing, dummy_password_hash, user),
+    fields(did = %body.did, user_id = %user.user_id)
+)]
+#[post("/dids")]
+pub async fn link_did(
+    body: web::Json<LinkBodyData>,
+    pool: web::Data<PgPool>,
+    hashing: web::Data<PasswordHashingSettings>,
+    dummy_password_hash: web::Data<DummyPasswordHash>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, LinkDidError> {
+    let body = body.into_inner();
+    resolve_did_key(&body.did).map_err(LinkDidError::InvalidDid)?;
+    verify_account_owner(
+        user.user_id,
+        body.password,
+        body.code.as_deref(),
+        &hashing,
+        &dummy_password_hash,
+        &pool,
+    )
+    .await?;
+    check_did_challenge(&body.did, &body.nonce, &body.signature, &pool).await?;
+    if link_user_did(user.user_id, &body.did, &pool).await? != user.user_id {
+        return Err(LinkDidError::Conflict);
+    }
+
+    let data = ResponseData {
+        data: body.did.clone(),
+        code: StatusCode::OK.as_u16(),
+        message: format!("{} is linked to your account", body.did),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+async fn verify_account_owner(
+    user_id: Uuid,
+    password: Option<Secret<String>>,
+    code: Option<&str>,
+    hashing: &PasswordHashingSettings,
+    dummy_password_hash: &DummyPasswordHash,
+    pool: &PgPool,
+) -> Result<(), LinkDidError> {
+    if is_totp_enabled(user_id, pool).await? {
+        let code = code.ok_or_else(|| {
+            LinkDidError::WrongProof(anyhow::anyhow!("The two-factor code is missing."))
+        })?;
+        if !verify_second_factor(user_id, code, pool).await? {
+            return Err(LinkDidError::WrongProof(anyhow::anyhow!(
+                "Wrong two-factor code."
+            )));
+        }
+        return Ok(());
+    }
+
+    let password = password.ok_or_else(|| {
+        LinkDidError::WrongProof(anyhow::anyhow!("The current password is missing."))
+    })?;
+    verify_current_password(user_id, password, hashing, dummy_password_hash, pool)
+        .await
+        .map_err(|e| match e {
+            AuthError::InvalidCredentials(_) => LinkDidError::WrongProof(e.into()),
+            AuthError::UnexpectedError(_) => LinkDidError::UnexpectedError(e.into()),
+        })
+}
@@ -0,0 +1,17 @@
+use actix_web::{get, http::header::ContentType, web, HttpResponse};
+use anyhow::anyhow;
+
+use crate::{authentication::JwtKeys, utils::e500};
+
+// Public keys downstream services verify our access tokens with
+#[tracing::instrument(name = "Get JSON Web Key Set", skip(jwt_keys))]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(jwt_keys: web::Data<JwtKeys>) -> Result<HttpResponse, actix_web::Error> {
+    let data = jwt_keys
+        .jwks()
+        .map_err(|e| e500(anyhow!("Failed to build the JWKS: {:?}", e)))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
@@ -1,13 +1,22 @@
 use actix_web::{
-    error::InternalError, http::header::ContentType, post, web, HttpResponse, ResponseError,
+    error::InternalError,
+    http::header::{ContentType, RETRY_AFTER},
+    post, web, HttpRequest, HttpResponse, ResponseError,
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use reqwest::StatusCode;
+use secrecy::ExposeSecret;
 use secrecy::Secret;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
-    authentication::{validate_credentials, AuthError, Credentials, JwtResponse},
+    authentication::{
+        check_login_lockout, clear_failed_logins, get_user_access, is_totp_enabled,
+        issue_mfa_challenge, issue_refresh_token, record_failed_login, validate_credentials,
+        AuthError, Credentials, DummyPasswordHash, JwtKeys, JwtResponse, LoginAttemptKey, Scope,
+    },
+    configuration::{LoginThrottleSettings, PasswordHashingSettings},
     domain::CustomerEmail,
     utils::{error_chain_fmt, ResponseData},
 };
@@ -16,13 +25,17 @@ use crate::{
 struct BodyData {
     username: String,
     password: Secret<String>,
+    // Space-delimited, the session gets every scope of the role when it is omitted
+    scope: Option<String>,
 }
 
 impl TryFrom<BodyData> for Credentials {
     type Error = String;
 
     fn try_from(value: BodyData) -> Result<Self, Self::Error> {
-        let BodyData { username, password } = value;
+        let BodyData {
+            username, password, ..
+        } = value;
         let username = CustomerEmail::parse(username)?;
 
         Ok(Credentials { username, password })
@@ -31,7 +44,7 @@ impl TryFrom<BodyData> for Credentials {
 
 #[tracing::instrument(
     name = "User login",
-    skip(body, pool),
+    skip(req, body, pool, jwt_keys, throttle, hashing, dummy_password_hash),
     fields(
         username=tracing::field::Empty,
         user_id=tracing::field::Empty,
@@ -39,9 +52,20 @@ impl TryFrom<BodyData> for Credentials {
 )]
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     body: web::Json<BodyData>,
     pool: web::Data<PgPool>,
+    jwt_keys: web::Data<JwtKeys>,
+    throttle: web::Data<LoginThrottleSettings>,
+    hashing: web::Data<PasswordHashingSettings>,
+    dummy_password_hash: web::Data<DummyPasswordHash>,
 ) -> Result<HttpResponse, InternalError<LoginError>> {
+    let requested_scopes = body
+        .scope
+        .as_deref()
+        .map(Scope::parse_list)
+        .transpose()
+        .map_err(|e| InternalError::new(LoginError::InvalidScope(e), StatusCode::BAD_REQUEST))?;
     let credentials: Credentials = body.0.try_into().map_err(|_| {
         InternalError::new(
             LoginError::AuthError(anyhow!("Invalid credential")),
@@ -51,19 +75,40 @@ pub async fn login(
 
     tracing::Span::current().record(
         "username",
-        &tracing::field::display(&credentials.username.as_ref()),
+        tracing::field::display(&credentials.username.as_ref()),
     );
-    match validate_credentials(credentials, &pool).await {
+    let attempt_keys = [
+        LoginAttemptKey::Account(credentials.username.as_ref().to_string()),
+        LoginAttemptKey::Ip(client_ip(&req, &throttle)),
+    ];
+    if let Some(retry_after) = check_login_lockout(&attempt_keys, &pool)
+        .await
+        .map_err(unexpected_error)?
+    {
+        return Err(too_many_attempts(retry_after));
+    }
+    match validate_credentials(credentials, &hashing, &dummy_password_hash, &pool).await {
         Ok(user_id) => {
-            tracing::Span::current().record("user_id", &tracing::field::display(&user_id));
-            let data = JwtResponse {
-                token_type: "Bearer".into(),
-                scope: "list of scopes separated by space".into(),
-                refresh_token: "refresh_token".into(),
-                access_token: "access_token".into(),
-                id_token: "id_token".into(),
-                expires_in: 4799,
-            };
+            tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+            // The failed logins are only cleared once the second factor is checked too
+            if is_totp_enabled(user_id, &pool)
+                .await
+                .map_err(unexpected_error)?
+            {
+                let challenge = issue_mfa_challenge(user_id, requested_scopes.as_deref(), &pool)
+                    .await
+                    .map_err(unexpected_error)?;
+
+                return Ok(HttpResponse::Ok()
+                    .content_type(ContentType::json())
+                    .json(challenge));
+            }
+            clear_failed_logins(&attempt_keys[0], &pool)
+                .await
+                .map_err(unexpected_error)?;
+            let data = start_session(user_id, requested_scopes, &pool, &jwt_keys)
+                .await
+                .map_err(unexpected_error)?;
 
             Ok(HttpResponse::Ok()
                 .content_type(ContentType::json())
@@ -72,12 +117,17 @@ pub async fn login(
         Err(e) => {
             let e = match e {
                 AuthError::UnexpectedError(_) => LoginError::UnexpectedError(e.into()),
-                AuthError::InvalidCredentials(_) => LoginError::AuthError(e.into()),
+                AuthError::InvalidCredentials(_) => {
+                    record_failed_login(&attempt_keys, &throttle, &pool)
+                        .await
+                        .map_err(unexpected_error)?;
+                    LoginError::AuthError(e.into())
+                }
             };
             let data = ResponseData {
                 data: "",
                 code: StatusCode::BAD_REQUEST.as_u16(),
-                message: format!("Invalid credentials"),
+                message: "Invalid credentials".to_string(),
             };
 
             let response = HttpResponse::BadRequest()
@@ -88,10 +138,93 @@ pub async fn login(
     }
 }
 
+/// The IP failed logins are counted against
+pub(crate) fn client_ip(req: &HttpRequest, throttle: &LoginThrottleSettings) -> String {
+    let connection_info = req.connection_info();
+    let ip = if throttle.trust_forwarded_for {
+        connection_info.realip_remote_addr()
+    } else {
+        connection_info.peer_addr()
+    };
+
+    ip.unwrap_or("unknown").to_string()
+}
+
+fn unexpected_error(e: anyhow::Error) -> InternalError<LoginError> {
+    InternalError::new(
+        LoginError::UnexpectedError(e),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+fn too_many_attempts(retry_after: chrono::Duration) -> InternalError<LoginError> {
+    let retry_after = retry_after_seconds(retry_after);
+
+    InternalError::from_response(
+        LoginError::TooManyAttempts(retry_after),
+        too_many_attempts_response(retry_after),
+    )
+}
+
+// Round up so that a client retrying on time is not locked out again
+pub(crate) fn retry_after_seconds(retry_after: chrono::Duration) -> i64 {
+    (retry_after.num_milliseconds() + 999) / 1000
+}
+
+pub(crate) fn too_many_attempts_response(retry_after: i64) -> HttpResponse {
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+        message: "Too many failed login attempts, try again later".to_string(),
+    };
+
+    HttpResponse::TooManyRequests()
+        .insert_header((RETRY_AFTER, retry_after.max(1).to_string()))
+        .content_type(ContentType::json())
+        .json(data)
+}
+
+/// Issue the access token and the first refresh token of a new session
+#[tracing::instrument(name = "Start a session", skip(pool, jwt_keys))]
+pub(crate) async fn start_session(
+    user_id: Uuid,
+    requested_scopes: Option<Vec<Scope>>,
+    pool: &PgPool,
+    jwt_keys: &JwtKeys,
+) -> Result<JwtResponse, anyhow::Error> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let refresh_token =
+        issue_refresh_token(&mut transaction, user_id, None, requested_scopes.as_deref()).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to start a session.")?;
+    let mut user = get_user_access(user_id, pool).await?;
+    if let Some(requested_scopes) = &requested_scopes {
+        user = user.restrict_scopes(requested_scopes);
+    }
+    let access_token = jwt_keys
+        .access_token(&user)
+        .map_err(|e| anyhow!("Failed to sign the access token: {:?}", e))?;
+
+    Ok(JwtResponse::bearer(
+        access_token,
+        refresh_token.expose_secret().to_string(),
+        &user.scopes,
+    ))
+}
+
 #[derive(thiserror::Error)]
 pub enum LoginError {
+    #[error("{0}")]
+    InvalidScope(String),
     #[error("Authentication failed")]
     AuthError(#[source] anyhow::Error),
+    #[error("Too many failed login attempts, retry after {0} seconds")]
+    TooManyAttempts(i64),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -105,7 +238,9 @@ impl std::fmt::Debug for LoginError {
 impl ResponseError for LoginError {
     fn status_code(&self) -> StatusCode {
         match self {
+            LoginError::InvalidScope(_) => StatusCode::BAD_REQUEST,
             LoginError::AuthError(_) => StatusCode::BAD_REQUEST,
+            LoginError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
             LoginError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -0,0 +1,65 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use secrecy::Secret;
+use sqlx::PgPool;
+
+use crate::{
+    authentication::{revoke_refresh_token, RefreshTokenError},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+struct BodyData {
+    refresh_token: Secret<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum LogoutError {
+    #[error("Invalid refresh token")]
+    AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for LogoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for LogoutError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            LogoutError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            LogoutError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<RefreshTokenError> for LogoutError {
+    fn from(e: RefreshTokenError) -> Self {
+        match e {
+            RefreshTokenError::InvalidToken(_) => LogoutError::AuthError(e.into()),
+            RefreshTokenError::UnexpectedError(_) => LogoutError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[tracing::instrument(name = "User logout", skip(body, pool))]
+#[post("/logout")]
+pub async fn logout(
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, LogoutError> {
+    revoke_refresh_token(&body.refresh_token, &pool).await?;
+
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully logged out".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
@@ -0,0 +1,241 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use crate::{
+    authentication::{
+        change_password, consume_password_reset_token, issue_password_reset_token,
+        revoke_user_refresh_tokens, verify_current_password, AuthError, AuthenticatedUser,
+        DummyPasswordHash, PasswordResetError,
+    },
+    configuration::PasswordHashingSettings,
+    domain::{CustomerEmail, UserPassword},
+    email_client::EmailClient,
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordBodyData {
+    current_password: Secret<String>,
+    new_password: Secret<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ForgotPasswordBodyData {
+    username: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ResetPasswordBodyData {
+    token: Secret<String>,
+    new_password: Secret<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum PasswordError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("The current password is incorrect")]
+    AuthError(#[source] anyhow::Error),
+    #[error("Invalid password reset token")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PasswordError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PasswordError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            PasswordError::AuthError(_) => StatusCode::BAD_REQUEST,
+            PasswordError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            PasswordError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<PasswordResetError> for PasswordError {
+    fn from(e: PasswordResetError) -> Self {
+        match e {
+            PasswordResetError::InvalidToken(_) => PasswordError::InvalidToken(e.into()),
+            PasswordResetError::UnexpectedError(_) => PasswordError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+/// Change the password of the logged in user, the current one is required.
+///
+/// Every session of the user is ended, the access token in use stays valid
+/// until it expires.
+#[tracing::instrument(
+    name = "Change password of the user",
+    skip(body, pool, hashing, dummy_password_hash, user),
+    fields(user_id = %user.user_id)
+)]
+#[post("/password")]
+pub async fn update_password(
+    body: web::Json<ChangePasswordBodyData>,
+    pool: web::Data<PgPool>,
+    hashing: web::Data<PasswordHashingSettings>,
+    dummy_password_hash: web::Data<DummyPasswordHash>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, PasswordError> {
+    let ChangePasswordBodyData {
+        current_password,
+        new_password,
+    } = body.0;
+    if current_password.expose_secret() == new_password.expose_secret() {
+        return Err(PasswordError::ValidationError(
+            "The new password must be different from the current one".to_string(),
+        ));
+    }
+    let new_password = UserPassword::parse(new_password).map_err(PasswordError::ValidationError)?;
+    verify_current_password(
+        user.user_id,
+        current_password,
+        &hashing,
+        &dummy_password_hash,
+        &pool,
+    )
+    .await
+    .map_err(|e| match e {
+        AuthError::InvalidCredentials(_) => PasswordError::AuthError(e.into()),
+        AuthError::UnexpectedError(_) => PasswordError::UnexpectedError(e.into()),
+    })?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    change_password(
+        &mut transaction,
+        user.user_id,
+        new_password.into(),
+        &hashing,
+    )
+    .await?;
+    revoke_user_refresh_tokens(&mut transaction, user.user_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to change a password.")?;
+
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::OK.as_u16(),
+        message: "Your password has been changed".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Email a password reset token to the user, to be sent along with the new
+/// password to `POST /password/reset`.
+///
+/// The response is the same whether the username is known or not.
+#[tracing::instrument(name = "Forgot password", skip(body, pool, email_client))]
+#[post("/password/forgot")]
+pub async fn forgot_password(
+    body: web::Json<ForgotPasswordBodyData>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+) -> Result<HttpResponse, PasswordError> {
+    let username = CustomerEmail::parse(body.0.username).map_err(PasswordError::ValidationError)?;
+    if let Some(reset_token) = issue_password_reset_token(username.as_ref(), &pool).await? {
+        send_password_reset_email(&email_client, &username, reset_token.expose_secret())
+            .await
+            .context("Failed to send a password reset email.")?;
+    }
+
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::OK.as_u16(),
+        message: format!(
+            "If {} is registered, a password reset email has been sent",
+            username.as_ref()
+        ),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Set a new password with the token of a reset email, every session of the
+/// user is ended.
+#[tracing::instrument(
+    name = "Reset password",
+    skip(body, pool, hashing),
+    fields(user_id=tracing::field::Empty)
+)]
+#[post("/password/reset")]
+pub async fn reset_password(
+    body: web::Json<ResetPasswordBodyData>,
+    pool: web::Data<PgPool>,
+    hashing: web::Data<PasswordHashingSettings>,
+) -> Result<HttpResponse, PasswordError> {
+    let ResetPasswordBodyData {
+        token,
+        new_password,
+    } = body.0;
+    let new_password = UserPassword::parse(new_password).map_err(PasswordError::ValidationError)?;
+    // The token is only used up along with the new password and the ended sessions
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let user_id = consume_password_reset_token(&mut transaction, &token).await?;
+    tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+    change_password(&mut transaction, user_id, new_password.into(), &hashing).await?;
+    revoke_user_refresh_tokens(&mut transaction, user_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to reset a password.")?;
+
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::OK.as_u16(),
+        message: "Your password has been reset".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+#[tracing::instrument(
+    name = "Send a password reset email",
+    skip(email_client, username, reset_token)
+)]
+async fn send_password_reset_email(
+    email_client: &EmailClient,
+    username: &CustomerEmail,
+    reset_token: &str,
+) -> Result<(), reqwest::Error> {
+    let html_body = format!(
+        "Someone asked to reset the password of your Rush Booking account.<br />\
+        Enter the code <code>{}</code> to choose a new password, \
+        it expires in one hour. Ignore this email if it was not you.",
+        reset_token
+    );
+    let plain_body = format!(
+        "Someone asked to reset the password of your Rush Booking account.\n\
+        Enter the code {} to choose a new password, it expires in one hour. \
+        Ignore this email if it was not you.",
+        reset_token
+    );
+
+    email_client
+        .send_email(username, "Reset your password", &html_body, &plain_body)
+        .await
+}
@@ -0,0 +1,363 @@
+use actix_web::{get, http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    authentication::{
+        compute_password_hash, generate_opaque_token, hash_opaque_token,
+        DEFAULT_REGISTRATION_TOKEN_TTL,
+    },
+    configuration::PasswordHashingSettings,
+    domain::{CustomerEmail, UserPassword},
+    email_client::EmailClient,
+    startup::ApplicationBaseUrl,
+    telemetry::spawn_blocking_with_tracing,
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct BodyData {
+    username: String,
+    password: Secret<String>,
+}
+
+pub struct NewUser {
+    username: CustomerEmail,
+    password: UserPassword,
+}
+
+impl TryFrom<BodyData> for NewUser {
+    type Error = String;
+
+    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let BodyData { username, password } = value;
+        let username = CustomerEmail::parse(username)?;
+        let password = UserPassword::parse(password)?;
+
+        Ok(NewUser { username, password })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum RegisterError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for RegisterError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RegisterError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            RegisterError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Register a new user",
+    skip(body, pool, email_client, base_url, hashing),
+    fields(user_id=tracing::field::Empty)
+)]
+#[post("/register")]
+pub async fn register(
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    hashing: web::Data<PasswordHashingSettings>,
+) -> Result<HttpResponse, RegisterError> {
+    let NewUser { username, password } =
+        body.0.try_into().map_err(RegisterError::ValidationError)?;
+    let hashing = hashing.get_ref().clone();
+    let password_hash =
+        spawn_blocking_with_tracing(move || compute_password_hash(password.into(), &hashing))
+            .await
+            .context("Failed to spawn blocking task.")?
+            .context("Failed to hash password")?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    match insert_user(&mut transaction, &username, password_hash)
+        .await
+        .context("Failed to insert new user in the database.")?
+    {
+        Some(user_id) => {
+            tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+            let registration_token = generate_opaque_token();
+            store_token(&mut transaction, user_id, &registration_token).await?;
+            send_confirmation_email(&email_client, &username, &base_url.0, &registration_token)
+                .await
+                .context("Failed to send a confirmation email.")?;
+        }
+        None => {
+            resend_to_registered_user(&mut transaction, &email_client, &username, &base_url.0)
+                .await?
+        }
+    }
+    // Only commit once the email is sent, so that a failed send can be retried
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store a new user.")?;
+
+    // The same answer whether or not the username was already registered,
+    // so that it does not tell which accounts exist
+    let data = ResponseData {
+        data: "",
+        code: StatusCode::OK.as_u16(),
+        message: format!(
+            "A confirmation email has been sent to {}",
+            username.as_ref()
+        ),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// A pending user gets a new confirmation link, replacing the previous ones,
+/// while an active user is told they already have an account. The password
+/// of the existing account is left as it is.
+#[tracing::instrument(
+    name = "Resend the registration email to a registered user",
+    skip(transaction, email_client, username, base_url),
+    fields(user_id=tracing::field::Empty)
+)]
+async fn resend_to_registered_user(
+    transaction: &mut Transaction<'_, Postgres>,
+    email_client: &EmailClient,
+    username: &CustomerEmail,
+    base_url: &str,
+) -> Result<(), anyhow::Error> {
+    let user = sqlx::query!(
+        r#"SELECT user_id, status FROM users WHERE username = $1 FOR UPDATE"#,
+        username.as_ref(),
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to retrieve the registered user.")?;
+    tracing::Span::current().record("user_id", tracing::field::display(&user.user_id));
+
+    if user.status == "pending_confirmation" {
+        let query = sqlx::query!(
+            r#"DELETE FROM registration_tokens WHERE user_id = $1"#,
+            user.user_id,
+        );
+        transaction
+            .execute(query)
+            .await
+            .context("Failed to delete the previous registration tokens.")?;
+        let registration_token = generate_opaque_token();
+        store_token(transaction, user.user_id, &registration_token).await?;
+        send_confirmation_email(email_client, username, base_url, &registration_token)
+            .await
+            .context("Failed to send a confirmation email.")?;
+    } else {
+        send_already_registered_email(email_client, username)
+            .await
+            .context("Failed to send an already registered email.")?;
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Saving new user details in database.",
+    skip(transaction, username, password_hash)
+)]
+// Returns `None` when the username is already registered
+async fn insert_user(
+    transaction: &mut Transaction<'_, Postgres>,
+    username: &CustomerEmail,
+    password_hash: Secret<String>,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let user_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO users (user_id, username, password_hash, status)
+        VALUES ($1, $2, $3, 'pending_confirmation')
+        ON CONFLICT (username) DO NOTHING
+        RETURNING user_id
+        "#,
+        Uuid::new_v4(),
+        username.as_ref(),
+        password_hash.expose_secret(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(user_id)
+}
+
+#[tracing::instrument(
+    name = "Store registration token in the database",
+    skip(transaction, registration_token)
+)]
+async fn store_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    registration_token: &str,
+) -> Result<(), anyhow::Error> {
+    let now = Utc::now();
+    let query = sqlx::query!(
+        r#"
+        INSERT INTO registration_tokens (token_hash, user_id, expires_at, created_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        hash_opaque_token(registration_token),
+        user_id,
+        now + Duration::seconds(DEFAULT_REGISTRATION_TOKEN_TTL as i64),
+        now,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to store the registration token.")?;
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Send a confirmation email to a new user",
+    skip(email_client, username, base_url, registration_token)
+)]
+async fn send_confirmation_email(
+    email_client: &EmailClient,
+    username: &CustomerEmail,
+    base_url: &str,
+    registration_token: &str,
+) -> Result<(), reqwest::Error> {
+    let confirmation_link = format!("{}/register/confirm?token={}", base_url, registration_token);
+    let html_body = format!(
+        "Welcome to Rush Booking!<br />\
+        Click <a href=\"{}\">here</a> to confirm your account.",
+        confirmation_link
+    );
+    let plain_body = format!(
+        "Welcome to Rush Booking!\nVisit {} to confirm your account.",
+        confirmation_link
+    );
+
+    email_client
+        .send_email(username, "Confirm your account", &html_body, &plain_body)
+        .await
+}
+
+#[tracing::instrument(
+    name = "Send an already registered email",
+    skip(email_client, username)
+)]
+async fn send_already_registered_email(
+    email_client: &EmailClient,
+    username: &CustomerEmail,
+) -> Result<(), reqwest::Error> {
+    let html_body = "Someone tried to register a Rush Booking account with this email.<br />\
+        You already have an account, log in or reset your password instead.";
+    let plain_body = "Someone tried to register a Rush Booking account with this email.\n\
+        You already have an account, log in or reset your password instead.";
+
+    email_client
+        .send_email(
+            username,
+            "You already have an account",
+            html_body,
+            plain_body,
+        )
+        .await
+}
+
+#[derive(serde::Deserialize)]
+pub struct Parameters {
+    token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum ConfirmRegistrationError {
+    #[error("Invalid registration token")]
+    InvalidToken(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ConfirmRegistrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ConfirmRegistrationError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ConfirmRegistrationError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            ConfirmRegistrationError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Activate the account of the confirmation link, the link works only once
+#[tracing::instrument(name = "Confirm a registration", skip(parameters, pool))]
+#[get("/register/confirm")]
+pub async fn confirm_registration(
+    parameters: web::Query<Parameters>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ConfirmRegistrationError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let stored_token = sqlx::query!(
+        r#"
+        DELETE FROM registration_tokens
+        WHERE token_hash = $1
+        RETURNING user_id, expires_at
+        "#,
+        hash_opaque_token(&parameters.token),
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to retrieve the registration token.")?
+    .ok_or_else(|| {
+        ConfirmRegistrationError::InvalidToken(anyhow::anyhow!("Unknown registration token."))
+    })?;
+    if stored_token.expires_at < Utc::now() {
+        return Err(ConfirmRegistrationError::InvalidToken(anyhow::anyhow!(
+            "The registration token is expired."
+        )));
+    }
+    let query = sqlx::query!(
+        r#"UPDATE users SET status = 'active' WHERE user_id = $1"#,
+        stored_token.user_id,
+    );
+    transaction
+        .execute(query)
+        .await
+        .context("Failed to activate the user.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to confirm a registration.")?;
+
+    let data = ResponseData {
+        data: stored_token.user_id,
+        code: StatusCode::OK.as_u16(),
+        message: "Your account is confirmed".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
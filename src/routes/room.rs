@@ -0,0 +1,3 @@
+mod availability;
+
+pub use availability::*;
@@ -0,0 +1,80 @@
+use actix_web::{get, http::header::ContentType, web, HttpResponse, ResponseError};
+use chrono::NaiveDate;
+use reqwest::StatusCode;
+use uuid::Uuid;
+
+use crate::{
+    domain::{RoomRepository, StayRange, MAX_NUMBER_OF_BEDS},
+    infrastructure::PgRoomRepository,
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct QueryData {
+    host_id: Uuid,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    guests: u16,
+}
+
+#[derive(thiserror::Error)]
+pub enum AvailabilityError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AvailabilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for AvailabilityError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AvailabilityError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AvailabilityError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Search available rooms", skip(room_repo))]
+#[get("/rooms/availability")]
+pub async fn search_available_rooms(
+    query: web::Query<QueryData>,
+    room_repo: web::Data<PgRoomRepository>,
+) -> Result<HttpResponse, AvailabilityError> {
+    let QueryData {
+        host_id,
+        check_in,
+        check_out,
+        guests,
+    } = query.into_inner();
+    let stay = StayRange::parse(check_in, check_out).map_err(AvailabilityError::ValidationError)?;
+    if guests == 0 {
+        return Err(AvailabilityError::ValidationError(
+            "Number of guests must be at least 1".to_string(),
+        ));
+    }
+    // No room has more beds, and the count is compared as a `SMALLINT`
+    if guests > MAX_NUMBER_OF_BEDS {
+        return Err(AvailabilityError::ValidationError(format!(
+            "Number of guests cannot be more than {}",
+            MAX_NUMBER_OF_BEDS
+        )));
+    }
+
+    let rooms = room_repo.find_available(host_id, &stay, guests).await?;
+
+    let response = ResponseData {
+        data: rooms,
+        code: StatusCode::OK.as_u16(),
+        message: "Successfully retrieving data".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(response))
+}
@@ -0,0 +1,81 @@
+use actix_web::{http::header::ContentType, post, web, HttpResponse, ResponseError};
+use anyhow::anyhow;
+use reqwest::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use crate::{
+    authentication::{
+        get_user_access, rotate_refresh_token, JwtKeys, JwtResponse, RefreshTokenError,
+    },
+    utils::error_chain_fmt,
+};
+
+#[derive(serde::Deserialize)]
+struct BodyData {
+    refresh_token: Secret<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum TokenRefreshError {
+    #[error("Invalid refresh token")]
+    AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TokenRefreshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for TokenRefreshError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TokenRefreshError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            TokenRefreshError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<RefreshTokenError> for TokenRefreshError {
+    fn from(e: RefreshTokenError) -> Self {
+        match e {
+            RefreshTokenError::InvalidToken(_) => TokenRefreshError::AuthError(e.into()),
+            RefreshTokenError::UnexpectedError(_) => TokenRefreshError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Refresh access token",
+    skip(body, pool, jwt_keys),
+    fields(user_id=tracing::field::Empty)
+)]
+#[post("/token/refresh")]
+pub async fn refresh_token(
+    body: web::Json<BodyData>,
+    pool: web::Data<PgPool>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> Result<HttpResponse, TokenRefreshError> {
+    let session = rotate_refresh_token(&body.refresh_token, &pool).await?;
+    tracing::Span::current().record("user_id", tracing::field::display(&session.user_id));
+    // Read the role again so that changes apply from the next refresh on
+    let mut user = get_user_access(session.user_id, &pool).await?;
+    if let Some(requested_scopes) = &session.requested_scopes {
+        user = user.restrict_scopes(requested_scopes);
+    }
+    let access_token = jwt_keys
+        .access_token(&user)
+        .map_err(|e| anyhow!("Failed to sign the access token: {:?}", e))?;
+    let data = JwtResponse::bearer(
+        access_token,
+        session.refresh_token.expose_secret().to_string(),
+        &user.scopes,
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
@@ -0,0 +1,247 @@
+use actix_web::{http::header::ContentType, post, web, HttpRequest, HttpResponse, ResponseError};
+use reqwest::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use super::{client_ip, retry_after_seconds, start_session, too_many_attempts_response};
+use crate::{
+    authentication::{
+        check_login_lockout, clear_failed_logins, confirm_totp_enrollment, get_mfa_challenge_user,
+        get_username, otpauth_uri, record_failed_login, start_totp_enrollment,
+        verify_current_password, verify_mfa_challenge, AuthError, AuthenticatedUser,
+        DummyPasswordHash, JwtKeys, LoginAttemptKey, TotpEnrollment, TotpError,
+    },
+    configuration::{LoginThrottleSettings, PasswordHashingSettings},
+    utils::{error_chain_fmt, ResponseData},
+};
+
+#[derive(serde::Deserialize)]
+pub struct EnrollTotpBodyData {
+    password: Secret<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmTotpBodyData {
+    code: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TotpLoginBodyData {
+    challenge_token: Secret<String>,
+    // A code of the authenticator app, or a recovery code
+    code: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum EnrollTotpError {
+    #[error("Two-factor authentication is already enabled")]
+    Conflict,
+    #[error("The current password is incorrect")]
+    AuthError(#[source] anyhow::Error),
+    #[error("Invalid two-factor authentication code")]
+    InvalidCode(#[source] anyhow::Error),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for EnrollTotpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for EnrollTotpError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EnrollTotpError::Conflict => StatusCode::CONFLICT,
+            EnrollTotpError::AuthError(_) => StatusCode::BAD_REQUEST,
+            EnrollTotpError::InvalidCode(_) => StatusCode::BAD_REQUEST,
+            EnrollTotpError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<AuthError> for EnrollTotpError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::InvalidCredentials(_) => EnrollTotpError::AuthError(e.into()),
+            AuthError::UnexpectedError(_) => EnrollTotpError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+impl From<TotpError> for EnrollTotpError {
+    fn from(e: TotpError) -> Self {
+        match e {
+            TotpError::AlreadyEnabled => EnrollTotpError::Conflict,
+            TotpError::InvalidCode(_) | TotpError::WrongCode(_) => {
+                EnrollTotpError::InvalidCode(e.into())
+            }
+            TotpError::UnexpectedError(_) => EnrollTotpError::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum TotpLoginError {
+    #[error("Authentication failed")]
+    AuthError(#[source] anyhow::Error),
+    #[error("Too many failed login attempts, retry after {0} seconds")]
+    TooManyAttempts(i64),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TotpLoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for TotpLoginError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TotpLoginError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            TotpLoginError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            TotpLoginError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            TotpLoginError::TooManyAttempts(retry_after) => {
+                too_many_attempts_response(*retry_after)
+            }
+            _ => HttpResponse::build(self.status_code())
+                .content_type(ContentType::plaintext())
+                .body(self.to_string()),
+        }
+    }
+}
+
+/// Generate a TOTP secret for the logged in user, it is enabled once a first
+/// code is confirmed. The current password is required.
+#[tracing::instrument(
+    name = "Enroll in TOTP",
+    skip(body, pool, hashing, dummy_password_hash, user),
+    fields(user_id = %user.user_id)
+)]
+#[post("/totp")]
+pub async fn enroll_totp(
+    body: web::Json<EnrollTotpBodyData>,
+    pool: web::Data<PgPool>,
+    hashing: web::Data<PasswordHashingSettings>,
+    dummy_password_hash: web::Data<DummyPasswordHash>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, EnrollTotpError> {
+    verify_current_password(
+        user.user_id,
+        body.0.password,
+        &hashing,
+        &dummy_password_hash,
+        &pool,
+    )
+    .await?;
+    let username = get_username(user.user_id, &pool).await?;
+    let secret = start_totp_enrollment(user.user_id, &pool).await?;
+
+    let data = ResponseData {
+        data: TotpEnrollment {
+            otpauth_uri: otpauth_uri(&username, secret.expose_secret()),
+            secret: secret.expose_secret().to_string(),
+        },
+        code: StatusCode::OK.as_u16(),
+        message: "Add the account to your authenticator app and confirm a code".to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Enable TOTP with a first code and hand out the recovery codes, they are
+/// not shown again
+#[tracing::instrument(
+    name = "Confirm TOTP enrollment",
+    skip(body, pool, user),
+    fields(user_id = %user.user_id)
+)]
+#[post("/totp/confirm")]
+pub async fn confirm_totp(
+    body: web::Json<ConfirmTotpBodyData>,
+    pool: web::Data<PgPool>,
+    user: web::ReqData<AuthenticatedUser>,
+) -> Result<HttpResponse, EnrollTotpError> {
+    let recovery_codes = confirm_totp_enrollment(user.user_id, body.code.trim(), &pool).await?;
+
+    let data = ResponseData {
+        data: recovery_codes
+            .iter()
+            .map(|code| code.expose_secret().to_string())
+            .collect::<Vec<_>>(),
+        code: StatusCode::OK.as_u16(),
+        message: "Two-factor authentication is enabled, store the recovery codes now \
+            as they will not be shown again"
+            .to_string(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
+
+/// Second step of `/login` for users with TOTP enabled, exchange the challenge
+/// token and a code for the tokens.
+///
+/// Accounts and IPs locked out by `/login` are locked out here as well.
+#[tracing::instrument(
+    name = "TOTP login",
+    skip(req, body, pool, jwt_keys, throttle),
+    fields(user_id=tracing::field::Empty)
+)]
+#[post("/login/totp")]
+pub async fn login_totp(
+    req: HttpRequest,
+    body: web::Json<TotpLoginBodyData>,
+    pool: web::Data<PgPool>,
+    jwt_keys: web::Data<JwtKeys>,
+    throttle: web::Data<LoginThrottleSettings>,
+) -> Result<HttpResponse, TotpLoginError> {
+    let mut attempt_keys = vec![LoginAttemptKey::Ip(client_ip(&req, &throttle))];
+    if let Some(user_id) = get_mfa_challenge_user(&body.challenge_token, &pool).await? {
+        let username = get_username(user_id, &pool).await?;
+        attempt_keys.push(LoginAttemptKey::Account(username));
+    }
+    if let Some(retry_after) = check_login_lockout(&attempt_keys, &pool).await? {
+        return Err(TotpLoginError::TooManyAttempts(retry_after_seconds(
+            retry_after,
+        )));
+    }
+    let (user_id, requested_scopes) =
+        match verify_mfa_challenge(&body.challenge_token, &body.code, &pool).await {
+            Ok(verified) => verified,
+            Err(TotpError::WrongCode(user_id)) => {
+                let username = get_username(user_id, &pool).await?;
+                let attempt_keys = [
+                    LoginAttemptKey::Account(username),
+                    LoginAttemptKey::Ip(client_ip(&req, &throttle)),
+                ];
+                record_failed_login(&attempt_keys, &throttle, &pool).await?;
+                return Err(TotpLoginError::AuthError(
+                    TotpError::WrongCode(user_id).into(),
+                ));
+            }
+            Err(e @ TotpError::UnexpectedError(_)) => {
+                return Err(TotpLoginError::UnexpectedError(e.into()))
+            }
+            Err(e) => return Err(TotpLoginError::AuthError(e.into())),
+        };
+    tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+    let username = get_username(user_id, &pool).await?;
+    clear_failed_logins(&LoginAttemptKey::Account(username), &pool).await?;
+    let data = start_session(user_id, requested_scopes, &pool, &jwt_keys).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .json(data))
+}
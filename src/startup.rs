@@ -1,17 +1,33 @@
 use actix_cors::Cors;
 use actix_web::{
     dev::Server,
-    web::{self, service, Data},
+    middleware::from_fn,
+    web::{self, Data},
     App, HttpServer,
 };
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use anyhow::Context;
+use reqwest::header::{HeaderName, AUTHORIZATION, CONTENT_TYPE};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::{io::Error, net::TcpListener};
 use tracing_actix_web::TracingLogger;
 
 use crate::{
-    configuration::{DatabaseSettings, Settings},
-    routes::{add_hosts, add_rooms, get_hosts, health_check, list_rooms, login},
+    authentication::{
+        reject_anonymous_users, reject_delegated_credentials, reject_guests, DummyPasswordHash,
+        JwtKeys,
+    },
+    configuration::{DatabaseSettings, LoginThrottleSettings, PasswordHashingSettings, Settings},
+    email_client::EmailClient,
+    infrastructure::{PgHostRepository, PgRoomRepository},
+    routes::{
+        add_api_keys, add_bookings, add_hosts, add_rooms, cancel_bookings, check_in_bookings,
+        check_out_bookings, confirm_bookings, confirm_registration, confirm_totp, delete_api_keys,
+        delete_hosts, delete_rooms, did_challenge, did_verify, enroll_totp, forgot_password,
+        get_hosts, guest_cancel_bookings, health_check, jwks, link_did, list_api_keys, list_hosts,
+        list_rooms, login, login_totp, logout, no_show_bookings, refresh_token, register,
+        reset_password, search_available_rooms, update_bookings, update_hosts, update_password,
+        update_rooms,
+    },
 };
 
 pub struct ApplicationBaseUrl(pub String);
@@ -26,17 +42,27 @@ impl Application {
             "{}:{}",
             configuration.application.host, configuration.application.port
         );
-        let listener = TcpListener::bind(address).expect(&format!(
-            "Failed to bind port {}",
-            configuration.application.port
-        ));
+        let listener = TcpListener::bind(address)
+            .unwrap_or_else(|_| panic!("Failed to bind port {}", configuration.application.port));
         let port = listener.local_addr().unwrap().port();
         let connection_pool = get_connection_pool(&configuration.database);
+        let jwt_keys = JwtKeys::from_settings(&configuration.jwt)
+            .map_err(|e| anyhow::anyhow!("Failed to load the JWT keys: {:?}", e))?;
+        let email_client = configuration.email_client.client();
+        let login_throttle = configuration.login_throttle;
+        let password_hashing = configuration.password_hashing;
+        password_hashing
+            .params()
+            .map_err(|e| anyhow::anyhow!("Invalid password hashing parameters: {:?}", e))?;
 
         let server = run(
             listener,
             configuration.application.base_url,
             connection_pool,
+            email_client,
+            jwt_keys,
+            login_throttle,
+            password_hashing,
         )
         .await?;
 
@@ -56,15 +82,33 @@ async fn run(
     listener: TcpListener,
     base_url: String,
     db_pool: PgPool,
+    email_client: EmailClient,
+    jwt_keys: JwtKeys,
+    login_throttle: LoginThrottleSettings,
+    password_hashing: PasswordHashingSettings,
 ) -> Result<Server, anyhow::Error> {
     let base_url = Data::new(ApplicationBaseUrl(base_url));
+    let email_client = Data::new(email_client);
+    let jwt_keys = Data::new(jwt_keys);
+    let login_throttle = Data::new(login_throttle);
+    let dummy_password_hash = Data::new(
+        DummyPasswordHash::compute(&password_hashing)
+            .context("Failed to compute the dummy password hash.")?,
+    );
+    let password_hashing = Data::new(password_hashing);
+    let host_repo = Data::new(PgHostRepository::new(db_pool.clone()));
+    let room_repo = Data::new(PgRoomRepository::new(db_pool.clone()));
     let db_pool = Data::new(db_pool);
     let server = HttpServer::new(move || {
         let cors = Cors::default()
             //Todo: Put to confguration and don't use localhost. it cause prelight problem in FE.
             .allowed_origin("http://127.0.0.1:8080")
-            .allowed_methods(vec!["GET", "POST", "OPTIONS"])
-            .allowed_headers(vec![AUTHORIZATION, CONTENT_TYPE])
+            .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE", "OPTIONS"])
+            .allowed_headers(vec![
+                AUTHORIZATION,
+                CONTENT_TYPE,
+                HeaderName::from_static("x-api-key"),
+            ])
             // .allowed_header(http::header::CONTENT_TYPE)
             .max_age(3600);
         App::new()
@@ -74,15 +118,59 @@ async fn run(
             .wrap(cors)
             .service(health_check)
             .service(login)
+            .service(login_totp)
+            .service(did_challenge)
+            .service(did_verify)
+            .service(jwks)
+            .service(refresh_token)
+            .service(logout)
+            .service(register)
+            .service(confirm_registration)
+            .service(forgot_password)
+            .service(reset_password)
+            .service(add_bookings)
+            .service(guest_cancel_bookings)
+            .service(update_bookings)
+            .service(search_available_rooms)
+            .service(
+                web::scope("/account")
+                    .wrap(from_fn(reject_delegated_credentials))
+                    .service(update_password)
+                    .service(enroll_totp)
+                    .service(confirm_totp)
+                    .service(link_did),
+            )
             .service(
                 web::scope("/admin")
+                    .wrap(from_fn(reject_guests))
+                    .wrap(from_fn(reject_anonymous_users))
+                    .service(list_hosts)
                     .service(get_hosts)
                     .service(add_hosts)
+                    .service(update_hosts)
+                    .service(delete_hosts)
                     .service(list_rooms)
-                    .service(add_rooms),
+                    .service(add_rooms)
+                    .service(update_rooms)
+                    .service(delete_rooms)
+                    .service(list_api_keys)
+                    .service(add_api_keys)
+                    .service(delete_api_keys)
+                    .service(confirm_bookings)
+                    .service(check_in_bookings)
+                    .service(check_out_bookings)
+                    .service(no_show_bookings)
+                    .service(cancel_bookings),
             )
             .app_data(base_url.clone())
             .app_data(db_pool.clone())
+            .app_data(host_repo.clone())
+            .app_data(room_repo.clone())
+            .app_data(email_client.clone())
+            .app_data(jwt_keys.clone())
+            .app_data(login_throttle.clone())
+            .app_data(password_hashing.clone())
+            .app_data(dummy_password_hash.clone())
     })
     .listen(listener)?
     .run();
@@ -0,0 +1,214 @@
+use chrono::{Duration, Utc};
+use rush_booking::authentication::{ApiKey, Role};
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+#[derive(serde::Deserialize)]
+struct CreatedApiKey {
+    api_key: ApiKey,
+    key: String,
+}
+
+async fn create_api_key(app: &TestApp, body: &serde_json::Value) -> CreatedApiKey {
+    let response = app.post_api_keys(body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    get_response_data_from_json(response).await.data
+}
+
+#[tokio::test]
+async fn api_key_is_accepted_by_the_admin_area() {
+    let app = spawn_app().await;
+    let created = create_api_key(
+        &app,
+        &serde_json::json!({ "name": "Channel manager", "scope": "rooms:read" }),
+    )
+    .await;
+
+    let response = app.get_hosts_with_api_key(&created.key).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(created.api_key.owner_id, app.test_user.user_id);
+    assert!(created.key.contains(&created.api_key.prefix));
+}
+
+#[tokio::test]
+async fn listing_api_keys_never_shows_the_secret() {
+    let app = spawn_app().await;
+    let created = create_api_key(
+        &app,
+        &serde_json::json!({ "name": "Channel manager", "scope": "rooms:read" }),
+    )
+    .await;
+
+    let response = app.get_api_keys().await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().await.unwrap();
+    assert!(body.contains(&created.api_key.id.to_string()));
+    assert!(!body.contains(&created.key));
+}
+
+#[tokio::test]
+async fn use_of_an_api_key_is_recorded() {
+    let app = spawn_app().await;
+    let created = create_api_key(
+        &app,
+        &serde_json::json!({ "name": "Channel manager", "scope": "rooms:read" }),
+    )
+    .await;
+    assert!(created.api_key.last_used_at.is_none());
+
+    app.get_hosts_with_api_key(&created.key).await;
+
+    let last_used_at = sqlx::query_scalar!(
+        "SELECT last_used_at FROM api_keys WHERE id = $1",
+        created.api_key.id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the API key.");
+    assert!(last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn revoked_or_expired_api_keys_are_rejected() {
+    let app = spawn_app().await;
+    let revoked = create_api_key(
+        &app,
+        &serde_json::json!({ "name": "Revoked", "scope": "rooms:read" }),
+    )
+    .await;
+    let response = app.delete_api_key(revoked.api_key.id).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let expired = create_api_key(
+        &app,
+        &serde_json::json!({
+            "name": "Expired",
+            "scope": "rooms:read",
+            "expires_at": Utc::now() + Duration::hours(1),
+        }),
+    )
+    .await;
+    sqlx::query!(
+        "UPDATE api_keys SET expires_at = now() - interval '1 minute' WHERE id = $1",
+        expired.api_key.id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to expire the API key.");
+    let test_cases = vec![
+        (revoked.key, "a revoked key"),
+        (expired.key, "an expired key"),
+        ("rbk_00000000_unknown".to_string(), "an unknown key"),
+        ("not-an-api-key".to_string(), "a malformed key"),
+    ];
+
+    for (key, error_message) in test_cases {
+        let response = app.get_hosts_with_api_key(&key).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "The API did not reject {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn api_key_is_limited_to_its_scopes() {
+    let app = spawn_app().await;
+    let created = create_api_key(
+        &app,
+        &serde_json::json!({ "name": "Bookings only", "scope": "bookings:write" }),
+    )
+    .await;
+
+    let response = app.get_hosts_with_api_key(&created.key).await;
+
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn revoking_an_unknown_api_key_returns_404() {
+    let app = spawn_app().await;
+
+    let response = app.delete_api_key(Uuid::new_v4()).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn create_api_key_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let guest = app.add_user(Role::Guest, vec![]).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "name": "", "scope": "rooms:read" }),
+            "empty name",
+        ),
+        (
+            serde_json::json!({ "name": "Channel manager", "scope": "" }),
+            "no scope",
+        ),
+        (
+            serde_json::json!({ "name": "Channel manager", "scope": "rooms:delete" }),
+            "unknown scope",
+        ),
+        (
+            serde_json::json!({
+                "name": "Channel manager",
+                "scope": "rooms:read",
+                "expires_at": Utc::now() - Duration::hours(1),
+            }),
+            "expiry in the past",
+        ),
+        (
+            serde_json::json!({
+                "name": "Channel manager",
+                "scope": "rooms:read",
+                "owner_id": Uuid::new_v4(),
+            }),
+            "unknown owner",
+        ),
+        (
+            serde_json::json!({
+                "name": "Channel manager",
+                "scope": "rooms:read",
+                "owner_id": guest.user_id,
+            }),
+            "scope the owner cannot be granted",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_api_keys(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the payload has {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn only_admins_manage_api_keys() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![host_id]).await;
+
+    let response = app
+        .api_client
+        .post(format!("{}/admin/api-keys", &app.address))
+        .bearer_auth(manager.access_token(&app.jwt_keys))
+        .json(&serde_json::json!({ "name": "Channel manager", "scope": "rooms:read" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 403);
+}
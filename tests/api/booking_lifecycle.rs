@@ -0,0 +1,143 @@
+use rush_booking::authentication::Role;
+use uuid::Uuid;
+
+use crate::helpers::{spawn_app, TestApp};
+
+async fn booking_status(app: &TestApp, booking_id: Uuid) -> String {
+    sqlx::query_scalar!("SELECT status FROM bookings WHERE id = $1", booking_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the booking.")
+}
+
+#[tokio::test]
+async fn booking_goes_from_pending_to_checked_out() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let booking_id = app.book_room(room_id, 0, 2).await;
+    assert_eq!(booking_status(&app, booking_id).await, "pending");
+    let test_cases = vec![
+        ("confirm", "confirmed"),
+        ("check-in", "checked_in"),
+        ("check-out", "checked_out"),
+    ];
+
+    for (action, status) in test_cases {
+        let response = app.post_booking_transition(booking_id, action).await;
+
+        assert_eq!(response.status().as_u16(), 200, "Failed to {}", action);
+        assert_eq!(booking_status(&app, booking_id).await, status);
+    }
+}
+
+#[tokio::test]
+async fn illegal_transitions_return_409() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let pending_id = app.book_room(room_id, 1, 2).await;
+    let checked_in_id = app.book_room(room_id, 2, 3).await;
+    app.post_booking_transition(checked_in_id, "confirm").await;
+    app.post_booking_transition(checked_in_id, "check-in").await;
+    let cancelled_id = app.book_room(room_id, 3, 4).await;
+    app.post_booking_transition(cancelled_id, "cancel").await;
+    let test_cases = vec![
+        (pending_id, "check-in", "checking in a pending booking"),
+        (pending_id, "check-out", "checking out a pending booking"),
+        (
+            pending_id,
+            "no-show",
+            "marking a pending booking as a no-show",
+        ),
+        (checked_in_id, "confirm", "confirming a checked in booking"),
+        (checked_in_id, "cancel", "cancelling a checked in booking"),
+        (cancelled_id, "confirm", "confirming a cancelled booking"),
+    ];
+
+    for (booking_id, action, error_message) in test_cases {
+        let response = app.post_booking_transition(booking_id, action).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            409,
+            "The API did not fail with 409 Conflict when {}",
+            error_message
+        );
+    }
+    assert_eq!(booking_status(&app, pending_id).await, "pending");
+    assert_eq!(booking_status(&app, checked_in_id).await, "checked_in");
+}
+
+#[tokio::test]
+async fn confirmed_booking_can_be_marked_as_a_no_show() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let booking_id = app.book_room(room_id, 0, 1).await;
+    app.post_booking_transition(booking_id, "confirm").await;
+
+    let response = app.post_booking_transition(booking_id, "no-show").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(booking_status(&app, booking_id).await, "no_show");
+}
+
+#[tokio::test]
+async fn cancelled_booking_frees_the_room() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let booking_id = app.book_room(room_id, 1, 3).await;
+
+    let response = app.post_booking_transition(booking_id, "cancel").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    app.book_room(room_id, 1, 3).await;
+}
+
+#[tokio::test]
+async fn transition_of_unknown_booking_returns_404() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_booking_transition(Uuid::new_v4(), "check-in")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn host_manager_handles_only_bookings_of_their_own_host() {
+    let app = spawn_app().await;
+    let own_host_id = app.create_host().await;
+    let other_host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![own_host_id]).await;
+    let token = manager.access_token(&app.jwt_keys);
+    let own_booking_id = app
+        .book_room(app.create_room_for_host(own_host_id, 2).await, 1, 2)
+        .await;
+    let other_booking_id = app
+        .book_room(app.create_room_for_host(other_host_id, 2).await, 1, 2)
+        .await;
+    let test_cases = vec![
+        (own_booking_id, 200, "their own host"),
+        (other_booking_id, 403, "another host"),
+    ];
+
+    for (booking_id, status, error_message) in test_cases {
+        let response = app
+            .api_client
+            .post(format!(
+                "{}/admin/bookings/{}/confirm",
+                &app.address, booking_id
+            ))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Unexpected status when a host manager confirms a booking of {}",
+            error_message
+        );
+    }
+}
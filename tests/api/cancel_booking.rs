@@ -0,0 +1,245 @@
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+#[derive(serde::Deserialize)]
+struct CancelledBooking {
+    status: String,
+    penalty: i64,
+    refund: i64,
+}
+
+/// A room for two at 100.00 a night under the given cancellation policy
+async fn create_priced_room(app: &TestApp, cancellation_policy: serde_json::Value) -> Uuid {
+    let host_id = app.create_host().await;
+    let response = app
+        .post_rooms(&serde_json::json!({
+            "name": "Standard room",
+            "description": "Room with city view",
+            "number_of_beds": 2,
+            "host_id": host_id,
+            "price_per_night": 10_000,
+            "cancellation_policy": cancellation_policy,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    get_response_data_from_json(response).await.data
+}
+
+// Free until a week before check-in, half of the total afterwards
+fn refundable_policy() -> serde_json::Value {
+    serde_json::json!({ "kind": "refundable", "free_until_days": 7, "penalty_percent": 50 })
+}
+
+#[tokio::test]
+async fn cancellation_charges_follow_the_policy_of_the_room() {
+    let app = spawn_app().await;
+    let refundable_room_id = create_priced_room(&app, refundable_policy()).await;
+    let non_refundable_room_id =
+        create_priced_room(&app, serde_json::json!({ "kind": "non_refundable" })).await;
+    let test_cases = vec![
+        (refundable_room_id, 30, 0, "a month ahead"),
+        (
+            refundable_room_id,
+            3,
+            10_000,
+            "after the free cancellation period",
+        ),
+        (
+            non_refundable_room_id,
+            30,
+            20_000,
+            "with a non-refundable policy",
+        ),
+    ];
+
+    for (room_id, check_in, penalty, error_message) in test_cases {
+        let booking_id = app.book_room(room_id, check_in, check_in + 2).await;
+
+        let response = app.post_cancel_booking(booking_id, "tom@example.com").await;
+
+        assert_eq!(response.status().as_u16(), 200);
+        let cancelled: CancelledBooking = get_response_data_from_json(response).await.data;
+        assert_eq!(cancelled.status, "cancelled");
+        assert_eq!(
+            cancelled.penalty, penalty,
+            "Unexpected penalty when cancelling {}",
+            error_message
+        );
+        assert_eq!(cancelled.refund, 20_000 - penalty);
+        let recorded = sqlx::query!(
+            "SELECT penalty, refund FROM booking_cancellations WHERE booking_id = $1",
+            booking_id
+        )
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the cancellation.");
+        assert_eq!(
+            (recorded.penalty, recorded.refund),
+            (penalty, 20_000 - penalty)
+        );
+    }
+}
+
+#[tokio::test]
+async fn booking_keeps_the_policy_it_was_made_with() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, refundable_policy()).await;
+    let booking_id = app.book_room(room_id, 30, 32).await;
+    let response = app
+        .patch_room(
+            room_id,
+            &serde_json::json!({
+                "price_per_night": 50_000,
+                "cancellation_policy": { "kind": "non_refundable" },
+            }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_cancel_booking(booking_id, "tom@example.com").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let cancelled: CancelledBooking = get_response_data_from_json(response).await.data;
+    assert_eq!((cancelled.penalty, cancelled.refund), (0, 20_000));
+}
+
+#[tokio::test]
+async fn cancel_booking_returns_404_for_another_guest_or_unknown_booking() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, refundable_policy()).await;
+    let booking_id = app.book_room(room_id, 30, 32).await;
+    let test_cases = vec![
+        (
+            booking_id,
+            "jerry@example.com",
+            "the email of another guest",
+        ),
+        (Uuid::new_v4(), "tom@example.com", "an unknown booking"),
+    ];
+
+    for (booking_id, guest_email, error_message) in test_cases {
+        let response = app.post_cancel_booking(booking_id, guest_email).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            404,
+            "The API did not fail with 404 Not Found with {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn admin_cancellation_applies_and_records_the_policy() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, refundable_policy()).await;
+    let booking_id = app.book_room(room_id, 3, 5).await;
+
+    let response = app.post_booking_transition(booking_id, "cancel").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let cancelled: CancelledBooking = get_response_data_from_json(response).await.data;
+    assert_eq!((cancelled.penalty, cancelled.refund), (10_000, 10_000));
+    let recorded = sqlx::query!(
+        "SELECT penalty, refund FROM booking_cancellations WHERE booking_id = $1",
+        booking_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the cancellation.");
+    assert_eq!((recorded.penalty, recorded.refund), (10_000, 10_000));
+}
+
+#[tokio::test]
+async fn cancel_booking_returns_409_once_cancelled_checked_in_or_started() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, refundable_policy()).await;
+    let cancelled_id = app.book_room(room_id, 30, 32).await;
+    app.post_cancel_booking(cancelled_id, "tom@example.com")
+        .await;
+    let checked_in_id = app.book_room(room_id, 0, 2).await;
+    app.post_booking_transition(checked_in_id, "confirm").await;
+    app.post_booking_transition(checked_in_id, "check-in").await;
+    let other_room_id = create_priced_room(&app, refundable_policy()).await;
+    let started_id = app.book_room(other_room_id, 0, 2).await;
+    app.post_booking_transition(started_id, "confirm").await;
+    let test_cases = vec![
+        (cancelled_id, "a cancelled booking"),
+        (checked_in_id, "a checked in booking"),
+        (started_id, "a confirmed booking starting today"),
+    ];
+
+    for (booking_id, error_message) in test_cases {
+        let response = app.post_cancel_booking(booking_id, "tom@example.com").await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            409,
+            "The API did not fail with 409 Conflict when cancelling {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn add_room_returns_400_for_invalid_price_or_policy() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "price_per_night": -1 }),
+            "a negative price",
+        ),
+        (
+            serde_json::json!({ "price_per_night": i64::MAX }),
+            "a price too high to total",
+        ),
+        (
+            serde_json::json!({
+                "cancellation_policy": {
+                    "kind": "refundable",
+                    "free_until_days": 7,
+                    "penalty_percent": 150,
+                },
+            }),
+            "a penalty above 100%",
+        ),
+        (
+            serde_json::json!({
+                "cancellation_policy": {
+                    "kind": "refundable",
+                    "free_until_days": u32::MAX,
+                    "penalty_percent": 50,
+                },
+            }),
+            "a free cancellation window too long to store",
+        ),
+        (
+            serde_json::json!({ "cancellation_policy": { "kind": "flexible" } }),
+            "an unknown policy",
+        ),
+    ];
+
+    for (invalid_fields, error_message) in test_cases {
+        let mut body = serde_json::json!({
+            "name": "Standard room",
+            "description": "Room with city view",
+            "number_of_beds": 2,
+            "host_id": host_id,
+        });
+        body.as_object_mut()
+            .unwrap()
+            .extend(invalid_fields.as_object().unwrap().clone());
+
+        let response = app.post_rooms(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request with {}",
+            error_message
+        );
+    }
+}
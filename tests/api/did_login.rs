@@ -0,0 +1,364 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ed25519_dalek::{Signer, SigningKey};
+use rand::rngs::OsRng;
+use rush_booking::authentication::{
+    decode, totp_code, verify, DidChallenge, JwtResponse, Role, TotpEnrollment,
+};
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+/// A did:key the way `init_data generate-user-dids` creates it
+fn generate_did() -> (String, SigningKey) {
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let mut bytes = vec![0xed, 0x01];
+    bytes.extend_from_slice(signing_key.verifying_key().as_bytes());
+    let did = format!("did:key:z{}", bs58::encode(bytes).into_string());
+
+    (did, signing_key)
+}
+
+async fn link_did(app: &TestApp, user_id: Uuid, did: &str) {
+    sqlx::query!(
+        "INSERT INTO user_dids (did, user_id) VALUES ($1, $2)",
+        did,
+        user_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to link the DID.");
+}
+
+async fn linked_user(app: &TestApp, did: &str) -> Option<Uuid> {
+    sqlx::query_scalar!("SELECT user_id FROM user_dids WHERE did = $1", did)
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to retrieve the user of the DID.")
+}
+
+async fn get_challenge(app: &TestApp, did: &str) -> DidChallenge {
+    let response = app.post_did_challenge(did).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response.json().await.unwrap()
+}
+
+fn answer(challenge: &DidChallenge, signing_key: &SigningKey) -> serde_json::Value {
+    let signature = signing_key.sign(challenge.nonce.as_bytes());
+    serde_json::json!({
+        "did": challenge.did,
+        "nonce": challenge.nonce,
+        "signature": URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+    })
+}
+
+/// The answer to a challenge along with the current password, to link the DID
+fn link_body(
+    challenge: &DidChallenge,
+    signing_key: &SigningKey,
+    password: &str,
+) -> serde_json::Value {
+    let mut body = answer(challenge, signing_key);
+    body["password"] = password.into();
+    body
+}
+
+#[tokio::test]
+async fn did_login_returns_the_tokens_of_the_linked_user() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let (did, signing_key) = generate_did();
+    link_did(&app, user.user_id, &did).await;
+    let challenge = get_challenge(&app, &did).await;
+
+    let response = app.post_did_verify(&answer(&challenge, &signing_key)).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens: JwtResponse = response.json().await.unwrap();
+    let token = decode(&tokens.access_token).expect("Failed to decode the access token");
+    assert!(verify(&token, &app.jwt_keys).is_ok());
+    assert_eq!(token.payload.sub, user.user_id.to_string());
+    assert_eq!(token.payload.role, Role::Guest);
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn did_challenge_returns_400_for_dids_other_than_ed25519_did_keys() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        ("did:web:example.com", "is another DID method"),
+        (
+            "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme",
+            "is a secp256k1 key",
+        ),
+        ("alice", "is not a DID"),
+    ];
+
+    for (did, error_message) in test_cases {
+        let response = app.post_did_challenge(did).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not return 400 when the DID {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn did_verify_returns_401_for_an_invalid_answer() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let (did, signing_key) = generate_did();
+    link_did(&app, user.user_id, &did).await;
+    let (other_did, other_signing_key) = generate_did();
+    let challenge = get_challenge(&app, &did).await;
+    let other_challenge = get_challenge(&app, &other_did).await;
+    let unlinked_challenge = get_challenge(&app, &other_did).await;
+    let wrong_signature = answer(&get_challenge(&app, &did).await, &other_signing_key);
+    let mut unknown_nonce = answer(&challenge, &signing_key);
+    unknown_nonce["nonce"] = "not-a-nonce".into();
+    // The challenge was issued for another DID
+    let mut foreign_nonce = answer(&other_challenge, &signing_key);
+    foreign_nonce["did"] = did.clone().into();
+    let test_cases = vec![
+        (wrong_signature, "is signed by another key"),
+        (unknown_nonce, "answers an unknown challenge"),
+        (foreign_nonce, "answers the challenge of another DID"),
+        (
+            answer(&unlinked_challenge, &other_signing_key),
+            "comes from a DID linked to no user",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_did_verify(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "The API did not return 401 when the answer {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn did_challenge_can_be_answered_only_once() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let (did, signing_key) = generate_did();
+    link_did(&app, user.user_id, &did).await;
+    let body = answer(&get_challenge(&app, &did).await, &signing_key);
+    let response = app.post_did_verify(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_did_verify(&body).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn did_verify_returns_401_for_an_expired_challenge() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let (did, signing_key) = generate_did();
+    link_did(&app, user.user_id, &did).await;
+    let challenge = get_challenge(&app, &did).await;
+    sqlx::query!(
+        "UPDATE did_challenges SET expires_at = now() - interval '1 second' WHERE nonce = $1",
+        challenge.nonce,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app.post_did_verify(&answer(&challenge, &signing_key)).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn user_can_link_a_did_and_log_in_with_it() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let token = user.access_token(&app.jwt_keys);
+    let (did, signing_key) = generate_did();
+    let challenge = get_challenge(&app, &did).await;
+
+    let response = app
+        .post_link_did(&link_body(&challenge, &signing_key, &user.password), &token)
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let challenge = get_challenge(&app, &did).await;
+    let response = app.post_did_verify(&answer(&challenge, &signing_key)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens: JwtResponse = response.json().await.unwrap();
+    let token = decode(&tokens.access_token).expect("Failed to decode the access token");
+    assert_eq!(token.payload.sub, user.user_id.to_string());
+}
+
+#[tokio::test]
+async fn link_did_requires_proof_of_the_key() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let token = user.access_token(&app.jwt_keys);
+    let (did, _) = generate_did();
+    let (_, other_signing_key) = generate_did();
+    let wrong_signature = link_body(
+        &get_challenge(&app, &did).await,
+        &other_signing_key,
+        &user.password,
+    );
+    let mut unknown_nonce = wrong_signature.clone();
+    unknown_nonce["nonce"] = "not-a-nonce".into();
+    let test_cases = vec![
+        (wrong_signature, "is signed by another key"),
+        (unknown_nonce, "answers an unknown challenge"),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_link_did(&body, &token).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "The API did not return 401 when the answer {}",
+            error_message
+        );
+    }
+    assert!(linked_user(&app, &did).await.is_none());
+}
+
+#[tokio::test]
+async fn link_did_requires_the_current_password() {
+    let app = spawn_app().await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let token = user.access_token(&app.jwt_keys);
+    let (did, signing_key) = generate_did();
+    let test_cases = vec![
+        (
+            link_body(
+                &get_challenge(&app, &did).await,
+                &signing_key,
+                &Uuid::new_v4().to_string(),
+            ),
+            "is wrong",
+        ),
+        (
+            answer(&get_challenge(&app, &did).await, &signing_key),
+            "is missing",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_link_did(&body, &token).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not return 400 when the password {}",
+            error_message
+        );
+    }
+    assert!(linked_user(&app, &did).await.is_none());
+}
+
+#[tokio::test]
+async fn link_did_requires_a_totp_code_once_totp_is_enabled() {
+    let app = spawn_app().await;
+    let response = app.post_enroll_totp(&app.test_user.password).await;
+    let enrollment: TotpEnrollment = get_response_data_from_json(response).await.data;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let response = app
+        .post_confirm_totp(&totp_code(&enrollment.secret, now).unwrap())
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let (did, signing_key) = generate_did();
+
+    let body = link_body(
+        &get_challenge(&app, &did).await,
+        &signing_key,
+        &app.test_user.password,
+    );
+    let response = app.post_link_did(&body, &app.access_token).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let mut body = answer(&get_challenge(&app, &did).await, &signing_key);
+    body["code"] = totp_code(&enrollment.secret, now + 30).unwrap().into();
+    let response = app.post_link_did(&body, &app.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(linked_user(&app, &did).await, Some(app.test_user.user_id));
+}
+
+#[tokio::test]
+async fn link_did_returns_409_for_a_did_of_another_user() {
+    let app = spawn_app().await;
+    let owner = app.add_user(Role::Guest, vec![]).await;
+    let user = app.add_user(Role::Guest, vec![]).await;
+    let (did, signing_key) = generate_did();
+    link_did(&app, owner.user_id, &did).await;
+    let challenge = get_challenge(&app, &did).await;
+
+    let response = app
+        .post_link_did(
+            &link_body(&challenge, &signing_key, &user.password),
+            &user.access_token(&app.jwt_keys),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 409);
+    assert_eq!(linked_user(&app, &did).await, Some(owner.user_id));
+}
+
+#[tokio::test]
+async fn anonymous_users_cannot_link_a_did() {
+    let app = spawn_app().await;
+    let (did, signing_key) = generate_did();
+    let challenge = get_challenge(&app, &did).await;
+
+    let response = app
+        .post_link_did(&answer(&challenge, &signing_key), "")
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn api_keys_and_restricted_sessions_cannot_link_a_did() {
+    let app = spawn_app().await;
+    let api_key = app
+        .create_api_key("rooms:read rooms:write bookings:write users:admin")
+        .await;
+    let restricted_token = app.restricted_access_token("rooms:read").await;
+    let (did, signing_key) = generate_did();
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/dids", &app.address))
+        .header("X-Api-Key", &api_key)
+        .json(&link_body(
+            &get_challenge(&app, &did).await,
+            &signing_key,
+            &app.test_user.password,
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_link_did(
+            &link_body(
+                &get_challenge(&app, &did).await,
+                &signing_key,
+                &app.test_user.password,
+            ),
+            &restricted_token,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(linked_user(&app, &did).await.is_none());
+}
@@ -5,6 +5,7 @@ use fake::Fake;
 use once_cell::sync::Lazy;
 use rush_booking::startup::get_connection_pool;
 use rush_booking::{
+    authentication::{sign, JwtKeys, JwtResponse, Role},
     configuration::get_configuration,
     startup::Application,
     telemetry::{get_subscriber, init_subscriber},
@@ -12,6 +13,7 @@ use rush_booking::{
 };
 use sqlx::PgPool;
 use uuid::Uuid;
+use wiremock::MockServer;
 
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
@@ -28,27 +30,58 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 
 pub struct TestApp {
     pub address: String,
-    pub db_pool: PgPool,
     pub port: u16,
+    pub db_pool: PgPool,
     pub api_client: reqwest::Client,
     pub test_user: TestUser,
+    pub access_token: String,
+    pub jwt_keys: JwtKeys,
+    pub email_server: MockServer,
+}
+
+/// Confirmation links embedded in the request to the email API.
+pub struct ConfirmationLinks {
+    pub html: reqwest::Url,
+    pub plain_text: reqwest::Url,
 }
 
 pub struct TestUser {
     pub user_id: Uuid,
     pub username: String,
     pub password: String,
+    pub role: Role,
+    pub host_ids: Vec<Uuid>,
 }
 
 impl TestUser {
+    /// The default test user is a platform admin
     pub fn generate() -> Self {
+        Self::generate_with_role(Role::Admin, vec![])
+    }
+
+    pub fn generate_with_role(role: Role, host_ids: Vec<Uuid>) -> Self {
         Self {
             user_id: Uuid::new_v4(),
-            username: SafeEmail().fake(),
+            // Tests share one database, so prefix the fake email to keep it unique
+            username: format!("{}.{}", Uuid::new_v4(), SafeEmail().fake::<String>()),
             password: Uuid::new_v4().to_string(),
+            role,
+            host_ids,
         }
     }
 
+    /// Sign an access token for the user, the same way `/login` does
+    pub fn access_token(&self, jwt_keys: &JwtKeys) -> String {
+        sign(
+            &jwt_keys
+                .new_payload(self.user_id.to_string())
+                .set_role(self.role, self.host_ids.clone())
+                .set_scope(&self.role.scopes()),
+            jwt_keys.private_key(),
+        )
+        .expect("Failed to sign the token")
+    }
+
     async fn store(&self, pool: &PgPool) {
         let salt = SaltString::generate(&mut rand::thread_rng());
         let password_hash = Argon2::new(
@@ -62,21 +95,47 @@ impl TestUser {
         // `dbg!` is a macro that prints and returns the value // of an expression for quick and dirty debugging.
         // dbg!(&password_hash);
         sqlx::query!(
-            "INSERT INTO users (user_id, username, password_hash) VALUES ($1, $2, $3)",
+            "INSERT INTO users (user_id, username, password_hash, role) VALUES ($1, $2, $3, $4)",
             self.user_id,
             self.username,
             password_hash,
+            self.role.as_ref(),
         )
         .execute(pool)
         .await
         .expect("Failed to create test users.");
+        for host_id in &self.host_ids {
+            sqlx::query!(
+                "INSERT INTO user_hosts (user_id, host_id) VALUES ($1, $2)",
+                self.user_id,
+                host_id,
+            )
+            .execute(pool)
+            .await
+            .expect("Failed to assign hosts to test users.");
+        }
     }
 }
 
 impl TestApp {
+    /// Store another user with the given role, the hosts must already exist
+    pub async fn add_user(&self, role: Role, host_ids: Vec<Uuid>) -> TestUser {
+        let user = TestUser::generate_with_role(role, host_ids);
+        user.store(&self.db_pool).await;
+        user
+    }
+
     pub async fn get_healthcheck(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/health_check", &self.address))
+            .get(format!("{}/health_check", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -84,25 +143,409 @@ impl TestApp {
 
     pub async fn post_hosts(&self, body: &serde_json::Value) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/hosts", &self.address))
+            .post(format!("{}/admin/hosts", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_rooms(&self, host_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/rooms", &self.address))
+            .bearer_auth(&self.access_token)
+            .query(&[("host_id", host_id)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_host(&self, host_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/hosts/{}", &self.address, host_id))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_hosts(&self, query: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/hosts", &self.address))
+            .bearer_auth(&self.access_token)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn patch_host(&self, host_id: Uuid, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .patch(format!("{}/admin/hosts/{}", &self.address, host_id))
+            .bearer_auth(&self.access_token)
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    pub async fn delete_host(&self, host_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .delete(format!("{}/admin/hosts/{}", &self.address, host_id))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_api_keys(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/api-keys", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_api_keys(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/api-keys", &self.address))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_api_key(&self, api_key_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .delete(format!("{}/admin/api-keys/{}", &self.address, api_key_id))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Create an API key of the test user and return the key
+    pub async fn create_api_key(&self, scope: &str) -> String {
+        let response = self
+            .post_api_keys(&serde_json::json!({ "name": "Integration", "scope": scope }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+        let created: serde_json::Value = get_response_data_from_json(response).await.data;
+
+        created["key"].as_str().unwrap().to_string()
+    }
+
+    /// Log the test user in with only the given scopes and return the access token
+    pub async fn restricted_access_token(&self, scope: &str) -> String {
+        let tokens: JwtResponse = self
+            .post_login(&serde_json::json!({
+                "username": &self.test_user.username,
+                "password": &self.test_user.password,
+                "scope": scope,
+            }))
+            .await
+            .json()
+            .await
+            .expect("Expected the tokens of a restricted session");
+
+        tokens.access_token
+    }
+
+    /// List hosts authenticated with an API key instead of an access token
+    pub async fn get_hosts_with_api_key(&self, key: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/hosts", &self.address))
+            .header("X-Api-Key", key)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn patch_room(&self, room_id: Uuid, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .patch(format!("{}/admin/rooms/{}", &self.address, room_id))
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_room(&self, room_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .delete(format!("{}/admin/rooms/{}", &self.address, room_id))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_rooms(&self, body: &serde_json::Value) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/rooms", &self.address))
+            .post(format!("{}/admin/rooms", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_bookings(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/bookings", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_room_availability(&self, query: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/rooms/availability", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn create_host(&self) -> Uuid {
+        let body = serde_json::json!({
+            "name": "Intercontinental",
+            "category": "hotel",
+        });
+        let response = self.post_hosts(&body).await;
+        get_response_data_from_json::<Uuid>(response).await.data
+    }
+
+    /// Create a new host with a single room and return the room id
+    pub async fn create_room(&self, number_of_beds: u16) -> Uuid {
+        let host_id = self.create_host().await;
+        self.create_room_for_host(host_id, number_of_beds).await
+    }
+
+    pub async fn create_room_for_host(&self, host_id: Uuid, number_of_beds: u16) -> Uuid {
+        let body = serde_json::json!({
+            "name": "Standard room",
+            "description": "Room with city view",
+            "number_of_beds": number_of_beds,
+            "host_id": host_id,
+        });
+        let response = self.post_rooms(&body).await;
+        get_response_data_from_json::<Uuid>(response).await.data
+    }
+
+    /// Book the room for the given nights, counted in days from today
+    pub async fn book_room(&self, room_id: Uuid, check_in: u64, check_out: u64) -> Uuid {
+        let today = chrono::Utc::now().date_naive();
+        let body = serde_json::json!({
+            "room_id": room_id,
+            "guest_name": "Tom",
+            "guest_email": "tom@example.com",
+            "check_in": today + chrono::Days::new(check_in),
+            "check_out": today + chrono::Days::new(check_out),
+        });
+        let response = self.post_bookings(&body).await;
+        assert!(response.status().is_success());
+        get_response_data_from_json::<Uuid>(response).await.data
+    }
+
+    pub async fn post_cancel_booking(
+        &self,
+        booking_id: Uuid,
+        guest_email: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/bookings/{}/cancel", &self.address, booking_id))
+            .json(&serde_json::json!({ "guest_email": guest_email }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn patch_booking(
+        &self,
+        booking_id: Uuid,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        self.api_client
+            .patch(format!("{}/bookings/{}", &self.address, booking_id))
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    /// Apply a lifecycle transition, e.g. `check-in`, to the booking
+    pub async fn post_booking_transition(
+        &self,
+        booking_id: Uuid,
+        action: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/bookings/{}/{}",
+                &self.address, booking_id, action
+            ))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_login(&self, body: &serde_json::Value) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login_totp(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/login/totp", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_enroll_totp(&self, password: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/account/totp", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(&serde_json::json!({ "password": password }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_confirm_totp(&self, code: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/account/totp/confirm", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(&serde_json::json!({ "code": code }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Log the test user in and return the issued tokens
+    pub async fn login(&self) -> JwtResponse {
+        let body = serde_json::json!({
+            "username": &self.test_user.username,
+            "password": &self.test_user.password,
+        });
+
+        self.post_login(&body)
+            .await
+            .json()
+            .await
+            .expect("Failed to parse the login response")
+    }
+
+    pub async fn post_refresh_token(&self, refresh_token: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/token/refresh", &self.address))
+            .json(&serde_json::json!({ "refresh_token": refresh_token }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout(&self, refresh_token: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/logout", &self.address))
+            .json(&serde_json::json!({ "refresh_token": refresh_token }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_register(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/register", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_change_password(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/account/password", &self.address))
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_forgot_password(&self, username: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/password/forgot", &self.address))
+            .json(&serde_json::json!({ "username": username }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_reset_password(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/password/reset", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Extract the confirmation links from a request to the email API
+    pub fn get_confirmation_links(&self, email_request: &wiremock::Request) -> ConfirmationLinks {
+        let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+        let get_link = |s: &str| {
+            let links: Vec<_> = linkify::LinkFinder::new()
+                .links(s)
+                .filter(|l| *l.kind() == linkify::LinkKind::Url)
+                .collect();
+            assert_eq!(links.len(), 1);
+            let raw_link = links[0].as_str().to_owned();
+            let mut confirmation_link = reqwest::Url::parse(&raw_link).unwrap();
+            // Make sure we don't call random APIs on the web
+            assert_eq!(confirmation_link.host_str().unwrap(), "127.0.0.1");
+            confirmation_link.set_port(Some(self.port)).unwrap();
+            confirmation_link
+        };
+
+        let html = get_link(body["HtmlBody"].as_str().unwrap());
+        let plain_text = get_link(body["TextBody"].as_str().unwrap());
+        ConfirmationLinks { html, plain_text }
+    }
+
+    pub async fn post_did_challenge(&self, did: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/auth/did/challenge", &self.address))
+            .json(&serde_json::json!({ "did": did }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_did_verify(&self, body: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/auth/did/verify", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_link_did(&self, body: &serde_json::Value, token: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/account/dids", &self.address))
+            .bearer_auth(token)
             .json(body)
             .send()
             .await
@@ -114,15 +557,24 @@ pub async fn spawn_app() -> TestApp {
     // Singleton Pattern
     Lazy::force(&TRACING);
 
+    // Failed logins are counted per client IP, give every test app its own
+    let client_ip = std::net::Ipv6Addr::from(Uuid::new_v4().as_u128());
+    let mut default_headers = reqwest::header::HeaderMap::new();
+    default_headers.insert("X-Forwarded-For", client_ip.to_string().parse().unwrap());
     let api_client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
         .cookie_store(true)
+        .default_headers(default_headers)
         .build()
         .unwrap();
+    // Launch a mock server to stand in for the email provider
+    let email_server = MockServer::start().await;
     let configuration = {
         let mut c = get_configuration().expect("Failed to read configuration");
         // Wildcard port, the system will find available port
         c.application.port = 0;
+        c.email_client.base_url = email_server.uri();
+        c.login_throttle.trust_forwarded_for = true;
         c
     };
     let app = Application::build(configuration.clone())
@@ -132,13 +584,19 @@ pub async fn spawn_app() -> TestApp {
     let address = format!("http://127.0.0.1:{}", port);
 
     // Run the application
-    let _ = tokio::spawn(app.run_until_stopped());
+    tokio::spawn(app.run_until_stopped());
+    let test_user = TestUser::generate();
+    let jwt_keys = JwtKeys::from_settings(&configuration.jwt).expect("Failed to load the JWT keys");
+    let access_token = test_user.access_token(&jwt_keys);
     let test_app = TestApp {
         db_pool: get_connection_pool(&configuration.database),
         address,
         port,
         api_client,
-        test_user: TestUser::generate(),
+        test_user,
+        access_token,
+        jwt_keys,
+        email_server,
     };
     // Add test user
     test_app.test_user.store(&test_app.db_pool).await;
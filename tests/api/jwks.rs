@@ -0,0 +1,42 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rush_booking::authentication::{decode, JwkSet};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn jwks_exposes_the_signing_key() {
+    let app = spawn_app().await;
+
+    let response = app.get_jwks().await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let jwks: JwkSet = response.json().await.unwrap();
+    let key = jwks
+        .keys
+        .iter()
+        .find(|key| key.kid == app.jwt_keys.signing_kid())
+        .expect("The signing key is missing from the JWKS");
+    assert_eq!(key.kty, "RSA");
+    assert_eq!(key.alg, "RS256");
+    assert_eq!(key.use_, "sig");
+    // 2048 bits modulus
+    assert_eq!(
+        URL_SAFE_NO_PAD
+            .decode(key.n.as_ref().unwrap())
+            .unwrap()
+            .len(),
+        256
+    );
+}
+
+#[tokio::test]
+async fn access_tokens_name_a_key_of_the_jwks() {
+    let app = spawn_app().await;
+    let jwks: JwkSet = app.get_jwks().await.json().await.unwrap();
+
+    let tokens = app.login().await;
+
+    let token = decode(&tokens.access_token).expect("Failed to decode the access token");
+    let kid = token.header.kid.expect("The access token has no kid");
+    assert!(jwks.keys.iter().any(|key| key.kid == kid));
+}
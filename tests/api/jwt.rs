@@ -2,3 +2,144 @@
 // How to refresh token
 // How to keep track of token, via token family
 // How to invalidate token family due to malicious action
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rush_booking::authentication::sign;
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app};
+
+#[tokio::test]
+async fn admin_requests_without_valid_token_are_rejected_with_401() {
+    let app = spawn_app().await;
+    let keys = &app.jwt_keys;
+    let user_id = app.test_user.user_id.to_string();
+    let expired_token = sign(
+        &keys
+            .new_payload(user_id.clone())
+            .set_exp(Duration::from_secs(0)),
+        keys.private_key(),
+    )
+    .expect("Failed to sign the token");
+    // Expiry is in seconds, wait until the token is in the past
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let token_of_unknown_subject = sign(&keys.new_payload("Tom".into()), keys.private_key())
+        .expect("Failed to sign the token");
+    let token_of_another_issuer = sign(
+        &keys.new_payload(user_id.clone()).set_issuer("someone_else"),
+        keys.private_key(),
+    )
+    .expect("Failed to sign the token");
+    let token_of_another_audience = sign(
+        &keys
+            .new_payload(user_id.clone())
+            .set_audience("another_api"),
+        keys.private_key(),
+    )
+    .expect("Failed to sign the token");
+    // Keep the signature of a valid token but swap in a payload we never signed
+    let tampered_token = {
+        let token = sign(&keys.new_payload(user_id.clone()), keys.private_key())
+            .expect("Failed to sign the token");
+        let segments = token.split('.').collect::<Vec<&str>>();
+        let payload = serde_json::to_string(
+            &keys
+                .new_payload(user_id.clone())
+                .set_exp(Duration::from_secs(86400)),
+        )
+        .unwrap();
+        format!(
+            "{}.{}.{}",
+            segments[0],
+            URL_SAFE_NO_PAD.encode(payload),
+            segments[2]
+        )
+    };
+    let test_cases = vec![
+        (None, "missing authorization header"),
+        (
+            Some("Basic dXNlcjpwYXNzd29yZA==".to_string()),
+            "basic scheme",
+        ),
+        (Some("Bearer not-a-token".to_string()), "malformed token"),
+        (
+            Some("Bearer !!!.e30.c2ln".to_string()),
+            "token with an undecodable header",
+        ),
+        (
+            Some("Bearer eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.bm90LWpzb24.c2ln".to_string()),
+            "token with a payload that is not JSON",
+        ),
+        (Some(format!("Bearer {}", expired_token)), "expired token"),
+        (
+            Some(format!("Bearer {}", token_of_unknown_subject)),
+            "subject is not a user id",
+        ),
+        (
+            Some(format!("Bearer {}", token_of_another_issuer)),
+            "token of another issuer",
+        ),
+        (
+            Some(format!("Bearer {}", token_of_another_audience)),
+            "token for another audience",
+        ),
+        (
+            Some(format!("Bearer {}", tampered_token)),
+            "tampered payload",
+        ),
+    ];
+
+    for (authorization, error_message) in test_cases {
+        let mut request = app.api_client.get(format!("{}/admin/hosts", &app.address));
+        if let Some(authorization) = authorization {
+            request = request.header("Authorization", authorization);
+        }
+        let response = request.send().await.expect("Failed to execute request.");
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "The API did not fail with 401 Unauthorized when the request had {}",
+            error_message
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("WWW-Authenticate")
+                .and_then(|h| h.to_str().ok()),
+            Some("Bearer")
+        );
+    }
+}
+
+#[tokio::test]
+async fn public_routes_do_not_require_token() {
+    let app = spawn_app().await;
+
+    let response = app.get_healthcheck().await;
+
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn add_room_records_the_authenticated_user() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let body = serde_json::json!({
+        "name":"Single bed room",
+        "description":"Single beds room with private pool",
+        "number_of_beds": 1,
+        "host_id": host_id,
+    });
+
+    let response = app.post_rooms(&body).await;
+
+    assert!(response.status().is_success());
+    let room_id = get_response_data_from_json::<Uuid>(response).await.data;
+    let saved = sqlx::query!("SELECT created_by FROM rooms WHERE id = $1", room_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved room.");
+    assert_eq!(saved.created_by, Some(app.test_user.user_id));
+}
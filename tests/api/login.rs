@@ -1,4 +1,8 @@
-use rush_booking::authentication::JwtResponse;
+use rush_booking::{
+    authentication::{compute_password_hash, decode, verify, JwtResponse, DEFAULT_TOKEN_TTL},
+    configuration::PasswordHashingSettings,
+};
+use secrecy::{ExposeSecret, Secret};
 use uuid::Uuid;
 
 use crate::helpers::{get_response_data_from_json, spawn_app};
@@ -70,5 +74,71 @@ async fn reponse_payload_has_token_after_login_success() {
 
     assert!(response.status().is_success());
     let login_resp: JwtResponse = response.json().await.unwrap();
-    assert!(login_resp.access_token.as_str().contains("access_token"));
+    assert_eq!(login_resp.token_type, "Bearer");
+    assert_eq!(login_resp.expires_in, DEFAULT_TOKEN_TTL);
+    let token = decode(&login_resp.access_token).expect("Failed to decode the access token");
+    assert!(verify(&token, &app.jwt_keys).is_ok());
+    assert_eq!(token.payload.sub, app.test_user.user_id.to_string());
+}
+
+#[tokio::test]
+async fn access_token_from_login_is_accepted_by_admin_routes() {
+    let app = spawn_app().await;
+    let body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password,
+    });
+    let response = app.post_login(&body).await;
+    let login_resp: JwtResponse = response.json().await.unwrap();
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(&login_resp.access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn login_upgrades_a_password_hash_weaker_than_the_current_settings() {
+    let app = spawn_app().await;
+    let weaker_hashing = PasswordHashingSettings {
+        m_cost: 4096,
+        t_cost: 1,
+        p_cost: 1,
+    };
+    let weaker_password_hash =
+        compute_password_hash(Secret::new(app.test_user.password.clone()), &weaker_hashing)
+            .unwrap();
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1 WHERE user_id = $2",
+        weaker_password_hash.expose_secret(),
+        app.test_user.user_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let tokens = app.login().await;
+
+    assert!(!tokens.access_token.is_empty());
+    let password_hash = sqlx::query_scalar!(
+        "SELECT password_hash FROM users WHERE user_id = $1",
+        app.test_user.user_id,
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert!(password_hash.starts_with("$argon2id$v=19$m=15000,t=2,p=1$"));
+    // The upgraded hash still matches the password
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
 }
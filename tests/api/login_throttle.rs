@@ -0,0 +1,108 @@
+use uuid::Uuid;
+
+use crate::helpers::{spawn_app, TestApp};
+
+fn login_body(username: &str, password: &str) -> serde_json::Value {
+    serde_json::json!({
+        "username": username,
+        "password": password,
+    })
+}
+
+async fn fail_logins(app: &TestApp, username: &str, times: usize) {
+    for _ in 0..times {
+        let response = app
+            .post_login(&login_body(username, &Uuid::new_v4().to_string()))
+            .await;
+        assert_eq!(response.status().as_u16(), 400);
+    }
+}
+
+fn retry_after(response: &reqwest::Response) -> u64 {
+    response
+        .headers()
+        .get("Retry-After")
+        .expect("The response has no Retry-After")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn account_is_locked_out_after_too_many_failed_logins() {
+    let app = spawn_app().await;
+    let username = app.test_user.username.clone();
+    fail_logins(&app, &username, 5).await;
+
+    // Even the right password is rejected while locked out
+    let response = app
+        .post_login(&login_body(&username, &app.test_user.password))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 429);
+    let retry_after = retry_after(&response);
+    assert!(retry_after > 0 && retry_after <= 30);
+}
+
+#[tokio::test]
+async fn every_failure_after_the_lockout_doubles_it() {
+    let app = spawn_app().await;
+    let username = app.test_user.username.clone();
+    fail_logins(&app, &username, 5).await;
+    // Let the first lockout run out
+    sqlx::query!(
+        "UPDATE login_attempts SET locked_until = now() WHERE key = $1",
+        format!("account:{}", username.to_lowercase()),
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    fail_logins(&app, &username, 1).await;
+
+    let response = app
+        .post_login(&login_body(&username, &app.test_user.password))
+        .await;
+    assert_eq!(response.status().as_u16(), 429);
+    let retry_after = retry_after(&response);
+    assert!(retry_after > 30 && retry_after <= 60);
+}
+
+#[tokio::test]
+async fn successful_login_clears_the_failed_logins_of_the_account() {
+    let app = spawn_app().await;
+    let username = app.test_user.username.clone();
+    fail_logins(&app, &username, 4).await;
+    let response = app
+        .post_login(&login_body(&username, &app.test_user.password))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    fail_logins(&app, &username, 4).await;
+
+    let response = app
+        .post_login(&login_body(&username, &app.test_user.password))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn client_ip_is_locked_out_after_too_many_failed_logins() {
+    let app = spawn_app().await;
+    // Stuffing credentials of many accounts, none of them is locked out
+    for _ in 0..20 {
+        let username = format!("{}@example.com", Uuid::new_v4());
+        fail_logins(&app, &username, 1).await;
+    }
+
+    let response = app
+        .post_login(&login_body(
+            &app.test_user.username,
+            &app.test_user.password,
+        ))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(retry_after(&response) > 0);
+}
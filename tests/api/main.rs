@@ -1,7 +1,22 @@
+mod api_keys;
+mod booking_lifecycle;
+mod cancel_booking;
+mod did_login;
 mod health_check;
 mod helpers;
+mod jwks;
 mod jwt;
 mod login;
+mod login_throttle;
+mod manage_booking;
 mod manage_host;
 mod manage_room;
+mod password;
 mod playground;
+mod refresh_token;
+mod register;
+mod roles;
+mod room_availability;
+mod scopes;
+mod totp;
+mod update_booking;
@@ -0,0 +1,208 @@
+use chrono::{Days, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app};
+
+fn days_from_today(days: u64) -> NaiveDate {
+    Utc::now().date_naive() + Days::new(days)
+}
+
+#[tokio::test]
+async fn add_booking_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "guest_name": "Tom",
+                "guest_email": "tom@example.com",
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+            }),
+            "missing room id",
+        ),
+        (
+            serde_json::json!({
+                "room_id": room_id,
+                "guest_email": "tom@example.com",
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+            }),
+            "missing guest name",
+        ),
+        (
+            serde_json::json!({
+                "room_id": room_id,
+                "guest_name": "Tom",
+                "guest_email": "not-an-email",
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+            }),
+            "invalid guest email",
+        ),
+        (
+            serde_json::json!({
+                "room_id": room_id,
+                "guest_name": "Tom",
+                "guest_email": "tom@example.com",
+                "check_in": "next monday",
+                "check_out": days_from_today(2),
+            }),
+            "invalid date format",
+        ),
+        (
+            serde_json::json!({
+                "room_id": room_id,
+                "guest_name": "Tom",
+                "guest_email": "tom@example.com",
+                "check_in": days_from_today(3),
+                "check_out": days_from_today(2),
+            }),
+            "check-out before check-in",
+        ),
+        (
+            serde_json::json!({
+                "room_id": room_id,
+                "guest_name": "Tom",
+                "guest_email": "tom@example.com",
+                "check_in": Utc::now().date_naive() - Days::new(2),
+                "check_out": days_from_today(2),
+            }),
+            "check-in in the past",
+        ),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        let response = app.post_bookings(&invalid_body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the payload was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn add_new_booking_for_a_room_should_success() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Tom",
+        "guest_email": "tom@example.com",
+        "check_in": days_from_today(1),
+        "check_out": days_from_today(3),
+    });
+
+    let response = app.post_bookings(&body).await;
+    assert!(response.status().is_success());
+    let booking_json = get_response_data_from_json::<Uuid>(response).await;
+
+    let saved = sqlx::query!(
+        "SELECT room_id, check_in, check_out, status FROM bookings WHERE id = $1",
+        booking_json.data,
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved booking.");
+    assert_eq!(saved.room_id, room_id);
+    assert_eq!(saved.check_in, days_from_today(1));
+    assert_eq!(saved.check_out, days_from_today(3));
+    assert_eq!(saved.status, "pending");
+}
+
+#[tokio::test]
+async fn add_overlapping_booking_for_the_same_room_returns_409() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Tom",
+        "guest_email": "tom@example.com",
+        "check_in": days_from_today(1),
+        "check_out": days_from_today(4),
+    });
+    let response = app.post_bookings(&body).await;
+    assert!(response.status().is_success());
+
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Jerry",
+        "guest_email": "jerry@example.com",
+        "check_in": days_from_today(3),
+        "check_out": days_from_today(5),
+    });
+    let response = app.post_bookings(&body).await;
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn add_booking_starting_on_the_previous_check_out_day_should_success() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Tom",
+        "guest_email": "tom@example.com",
+        "check_in": days_from_today(1),
+        "check_out": days_from_today(3),
+    });
+    let response = app.post_bookings(&body).await;
+    assert!(response.status().is_success());
+
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Jerry",
+        "guest_email": "jerry@example.com",
+        "check_in": days_from_today(3),
+        "check_out": days_from_today(5),
+    });
+    let response = app.post_bookings(&body).await;
+
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn only_one_of_concurrent_overlapping_bookings_should_success() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(2).await;
+    let concurrent_requests = 10;
+
+    let handles = (0..concurrent_requests)
+        .map(|i| {
+            let client = app.api_client.clone();
+            let url = format!("{}/bookings", &app.address);
+            let body = serde_json::json!({
+                "room_id": room_id,
+                "guest_name": format!("Guest {}", i),
+                "guest_email": "guest@example.com",
+                // Every request shares at least the second night
+                "check_in": days_from_today(1 + i % 2),
+                "check_out": days_from_today(3),
+            });
+            tokio::spawn(async move {
+                client
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .expect("Failed to execute request.")
+                    .status()
+                    .as_u16()
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut statuses = Vec::new();
+    for handle in handles {
+        statuses.push(handle.await.unwrap());
+    }
+
+    assert_eq!(statuses.iter().filter(|s| **s == 200).count(), 1);
+    assert_eq!(
+        statuses.iter().filter(|s| **s == 409).count(),
+        concurrent_requests as usize - 1
+    );
+}
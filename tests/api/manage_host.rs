@@ -1,4 +1,6 @@
-use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app};
 
 #[tokio::test]
 async fn add_host_returns_400_for_invalid_data() {
@@ -53,3 +55,211 @@ async fn add_new_host_type_hotel_should_success() {
     let response_body = response.text().await.unwrap();
     assert!(response_body.contains(&status_code.to_string()));
 }
+
+#[tokio::test]
+async fn get_host_returns_the_host_with_its_rooms() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let room_id = app.create_room_for_host(host_id, 2).await;
+
+    let response = app.get_host(&host_id.to_string()).await;
+
+    assert!(response.status().is_success());
+    let host = get_response_data_from_json::<serde_json::Value>(response)
+        .await
+        .data;
+    assert_eq!(host["id"].as_str(), Some(host_id.to_string().as_str()));
+    assert_eq!(host["category"].as_str(), Some("hotel"));
+    assert_eq!(host["rooms"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        host["rooms"][0]["id"].as_str(),
+        Some(room_id.to_string().as_str())
+    );
+}
+
+#[tokio::test]
+async fn get_host_returns_404_for_unknown_host() {
+    let app = spawn_app().await;
+
+    let response = app.get_host(&Uuid::new_v4().to_string()).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn list_hosts_returns_400_for_invalid_query() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "category": "prison" }),
+            "unknown category",
+        ),
+        (serde_json::json!({ "page": 0 }), "page is zero"),
+        (
+            serde_json::json!({ "per_page": 1000 }),
+            "too many items per page",
+        ),
+    ];
+
+    for (invalid_query, error_message) in test_cases {
+        let response = app.get_hosts(&invalid_query).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the query was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_hosts_is_paginated_and_filtered_by_category() {
+    let app = spawn_app().await;
+    for _ in 0..3 {
+        let body = serde_json::json!({
+            "name":"Cozy house",
+            "category":"guest_house",
+        });
+        let response = app.post_hosts(&body).await;
+        assert!(response.status().is_success());
+    }
+
+    let query = serde_json::json!({
+        "category": "guest_house",
+        "page": 1,
+        "per_page": 2,
+    });
+    let response = app.get_hosts(&query).await;
+
+    assert!(response.status().is_success());
+    let page = get_response_data_from_json::<serde_json::Value>(response)
+        .await
+        .data;
+    let hosts = page["items"].as_array().unwrap();
+    assert_eq!(hosts.len(), 2);
+    assert!(hosts
+        .iter()
+        .all(|host| host["category"].as_str() == Some("guest_house")));
+    assert!(page["total"].as_u64().unwrap() >= 3);
+}
+
+#[tokio::test]
+async fn update_host_changes_only_the_given_fields() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+
+    let body = serde_json::json!({ "name": "Intercontinental Saigon" });
+    let response = app.patch_host(host_id, &body).await;
+
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT name, category FROM hosts WHERE id = $1", host_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch saved host.");
+    assert_eq!(saved.name, "Intercontinental Saigon");
+    assert_eq!(saved.category, "hotel");
+}
+
+#[tokio::test]
+async fn update_host_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let test_cases = vec![
+        (serde_json::json!({}), "nothing to update"),
+        (
+            serde_json::json!({ "category": "prison" }),
+            "unknown category",
+        ),
+        (serde_json::json!({ "name": "<script>" }), "invalid name"),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        let response = app.patch_host(host_id, &invalid_body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the payload was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_host_returns_404_for_unknown_host() {
+    let app = spawn_app().await;
+
+    let body = serde_json::json!({ "category": "guest_house" });
+    let response = app.patch_host(Uuid::new_v4(), &body).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn delete_host_removes_the_host_and_its_rooms() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let room_id = app.create_room_for_host(host_id, 2).await;
+
+    let response = app.delete_host(host_id).await;
+
+    assert!(response.status().is_success());
+    let response = app.get_host(&host_id.to_string()).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let room = sqlx::query!("SELECT deleted_at FROM rooms WHERE id = $1", room_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch room.");
+    assert!(room.deleted_at.is_some());
+}
+
+#[tokio::test]
+async fn delete_host_keeps_the_past_bookings_of_its_rooms() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let room_id = app.create_room_for_host(host_id, 2).await;
+    let booking_id = app.book_room(room_id, 1, 3).await;
+    let response = app.post_cancel_booking(booking_id, "tom@example.com").await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.delete_host(host_id).await;
+
+    assert!(response.status().is_success());
+    let booking = sqlx::query!("SELECT status FROM bookings WHERE id = $1", booking_id)
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch booking.");
+    assert!(booking.is_some());
+    let cancellation = sqlx::query!(
+        "SELECT booking_id FROM booking_cancellations WHERE booking_id = $1",
+        booking_id
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .expect("Failed to fetch cancellation.");
+    assert!(cancellation.is_some());
+}
+
+#[tokio::test]
+async fn delete_host_with_upcoming_bookings_returns_409() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let room_id = app.create_room_for_host(host_id, 2).await;
+    app.book_room(room_id, 1, 3).await;
+
+    let response = app.delete_host(host_id).await;
+
+    assert_eq!(response.status().as_u16(), 409);
+    let response = app.get_host(&host_id.to_string()).await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn delete_host_returns_404_for_unknown_host() {
+    let app = spawn_app().await;
+
+    let response = app.delete_host(Uuid::new_v4()).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -35,6 +35,24 @@ async fn add_room_returns_400_for_invalid_data() {
             }),
             "missing host id",
         ),
+        (
+            serde_json::json!({
+                "name":"Double beds room",
+                "description":"Single beds room with private pool",
+                "number_of_beds": 0,
+                "host_id": Uuid::new_v4(),
+            }),
+            "no beds",
+        ),
+        (
+            serde_json::json!({
+                "name":"Double beds room",
+                "description":"Single beds room with private pool",
+                "number_of_beds": 40000,
+                "host_id": Uuid::new_v4(),
+            }),
+            "more beds than can be stored",
+        ),
     ];
 
     for (invalid_body, error_message) in test_cases {
@@ -90,3 +108,192 @@ async fn add_new_room_to_a_hotel_should_success() {
         .as_str()
         .contains("Successfully created new room"));
 }
+
+#[tokio::test]
+async fn list_rooms_returns_rooms_created_for_the_host() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let other_host_id = app.create_host().await;
+    let body = serde_json::json!({
+        "name":"Double bed room",
+        "description":"Double beds room with sea view",
+        "number_of_beds": 2,
+        "host_id": host_id,
+    });
+    let response = app.post_rooms(&body).await;
+    let room_id = get_response_data_from_json::<Uuid>(response).await.data;
+    app.create_room_for_host(other_host_id, 1).await;
+
+    let response = app.get_rooms(host_id).await;
+
+    assert!(response.status().is_success());
+    let rooms = get_response_data_from_json::<Vec<serde_json::Value>>(response)
+        .await
+        .data;
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0]["id"].as_str(), Some(room_id.to_string().as_str()));
+    assert_eq!(rooms[0]["name"].as_str(), Some("Double bed room"));
+    assert_eq!(rooms[0]["number_of_beds"].as_u64(), Some(2));
+    assert_eq!(
+        rooms[0]["container"]["id"].as_str(),
+        Some(host_id.to_string().as_str())
+    );
+}
+
+#[tokio::test]
+async fn list_rooms_returns_400_without_host_id() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/rooms", &app.address))
+        .bearer_auth(&app.access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn update_room_changes_only_the_given_fields() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(1).await;
+
+    let body = serde_json::json!({
+        "description": "Renovated room with two beds",
+        "number_of_beds": 2,
+    });
+    let response = app.patch_room(room_id, &body).await;
+
+    assert!(response.status().is_success());
+    let saved = sqlx::query!(
+        "SELECT name, description, number_of_beds FROM rooms WHERE id = $1",
+        room_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved room.");
+    assert_eq!(saved.name, "Standard room");
+    assert_eq!(saved.description, "Renovated room with two beds");
+    assert_eq!(saved.number_of_beds, 2);
+}
+
+#[tokio::test]
+async fn update_room_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(1).await;
+    let test_cases = vec![
+        (serde_json::json!({}), "nothing to update"),
+        (serde_json::json!({ "number_of_beds": 0 }), "no beds"),
+        (
+            serde_json::json!({ "number_of_beds": 40000 }),
+            "more beds than can be stored",
+        ),
+        (serde_json::json!({ "name": "" }), "empty name"),
+        (
+            serde_json::json!({ "price_per_night": i64::MAX }),
+            "a price too high to total",
+        ),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        let response = app.patch_room(room_id, &invalid_body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the payload was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_room_returns_404_for_unknown_room() {
+    let app = spawn_app().await;
+
+    let body = serde_json::json!({ "number_of_beds": 3 });
+    let response = app.patch_room(Uuid::new_v4(), &body).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn delete_room_without_upcoming_bookings_should_success() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(1).await;
+
+    let response = app.delete_room(room_id).await;
+
+    assert!(response.status().is_success());
+    let room = sqlx::query!("SELECT deleted_at FROM rooms WHERE id = $1", room_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch room.");
+    assert!(room.deleted_at.is_some());
+}
+
+#[tokio::test]
+async fn deleted_room_keeps_its_past_stays_and_cannot_be_booked() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(1).await;
+    let booking_id = app.book_room(room_id, 1, 3).await;
+    sqlx::query!(
+        r#"
+        UPDATE bookings
+        SET check_in = CURRENT_DATE - 5, check_out = CURRENT_DATE - 3, status = 'checked_out'
+        WHERE id = $1
+        "#,
+        booking_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to move the booking to the past.");
+
+    let response = app.delete_room(room_id).await;
+
+    assert!(response.status().is_success());
+    let booking = sqlx::query!("SELECT status FROM bookings WHERE id = $1", booking_id)
+        .fetch_optional(&app.db_pool)
+        .await
+        .expect("Failed to fetch booking.");
+    assert!(booking.is_some());
+    let response = app.delete_room(room_id).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let response = app
+        .patch_room(room_id, &serde_json::json!({ "number_of_beds": 2 }))
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+    let today = chrono::Utc::now().date_naive();
+    let response = app
+        .post_bookings(&serde_json::json!({
+            "room_id": room_id,
+            "guest_name": "Tom",
+            "guest_email": "tom@example.com",
+            "check_in": today + chrono::Days::new(10),
+            "check_out": today + chrono::Days::new(12),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn delete_room_with_upcoming_bookings_returns_409() {
+    let app = spawn_app().await;
+    let room_id = app.create_room(1).await;
+    app.book_room(room_id, 5, 7).await;
+
+    let response = app.delete_room(room_id).await;
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn delete_room_returns_404_for_unknown_room() {
+    let app = spawn_app().await;
+
+    let response = app.delete_room(Uuid::new_v4()).await;
+
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -0,0 +1,242 @@
+use uuid::Uuid;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{spawn_app, TestApp};
+
+async fn mount_email_server(app: &TestApp) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+}
+
+/// Ask for a reset email for the test user and return the token it contains
+async fn request_reset_token(app: &TestApp) -> String {
+    let response = app.post_forgot_password(&app.test_user.username).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let email_requests = app.email_server.received_requests().await.unwrap();
+    let body: serde_json::Value =
+        serde_json::from_slice(&email_requests.last().unwrap().body).unwrap();
+    let html_token = token_of(body["HtmlBody"].as_str().unwrap(), "<code>");
+    let plain_text_token = token_of(body["TextBody"].as_str().unwrap(), "Enter the code ");
+    assert_eq!(html_token, plain_text_token);
+    html_token
+}
+
+fn token_of(email_body: &str, prefix: &str) -> String {
+    let (_, rest) = email_body
+        .split_once(prefix)
+        .expect("The email has no token");
+    rest.chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+fn login_body(app: &TestApp, password: &str) -> serde_json::Value {
+    serde_json::json!({
+        "username": &app.test_user.username,
+        "password": password,
+    })
+}
+
+#[tokio::test]
+async fn change_password_requires_an_access_token() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/password", &app.address))
+        .json(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": Uuid::new_v4().to_string(),
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn change_password_returns_400_for_invalid_passwords() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "current_password": Uuid::new_v4().to_string(),
+                "new_password": Uuid::new_v4().to_string(),
+            }),
+            "the current password is wrong",
+        ),
+        (
+            serde_json::json!({
+                "current_password": &app.test_user.password,
+                "new_password": "short",
+            }),
+            "the new password is too short",
+        ),
+        (
+            serde_json::json!({
+                "current_password": &app.test_user.password,
+                "new_password": &app.test_user.password,
+            }),
+            "the new password is the current one",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_change_password(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not return 400 when {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn changed_password_replaces_the_current_one() {
+    let app = spawn_app().await;
+    let new_password = Uuid::new_v4().to_string();
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": &new_password,
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_login(&login_body(&app, &new_password)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app
+        .post_login(&login_body(&app, &app.test_user.password))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn changed_password_ends_the_other_sessions() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn forgot_password_sends_no_email_to_unknown_users() {
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_forgot_password(&format!("{}@example.com", Uuid::new_v4()))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn reset_password_sets_a_new_password_and_ends_sessions() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let tokens = app.login().await;
+    let token = request_reset_token(&app).await;
+    let new_password = Uuid::new_v4().to_string();
+
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": token,
+            "new_password": &new_password,
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_login(&login_body(&app, &new_password)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn reset_tokens_work_only_once() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let token = request_reset_token(&app).await;
+    let other_token = request_reset_token(&app).await;
+    let body = serde_json::json!({
+        "token": token,
+        "new_password": Uuid::new_v4().to_string(),
+    });
+    let response = app.post_reset_password(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_reset_password(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+    // The other pending tokens are used up as well
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": other_token,
+            "new_password": Uuid::new_v4().to_string(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn reset_password_returns_401_for_an_expired_token() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let token = request_reset_token(&app).await;
+    sqlx::query!(
+        "UPDATE password_reset_tokens SET expires_at = now() - interval '1 second' WHERE user_id = $1",
+        app.test_user.user_id,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": token,
+            "new_password": Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn reset_password_returns_400_for_a_weak_password() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let token = request_reset_token(&app).await;
+
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": token,
+            "new_password": "short",
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
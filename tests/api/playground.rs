@@ -19,11 +19,21 @@ fn clone_on_write() {
         Cow::Owned(_) => println!("Move happened"),
     }
 
-    assert_eq!(input, ['a', 'd', 'c']);
+    // Upper case input is only borrowed, so the original is untouched
+    assert_eq!(input, ['A', 'B', 'C']);
 }
 
 pub struct Immutable<T>(T);
 
+impl<T> Clone for Immutable<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
 impl<T> Copy for Immutable<T> where T: Copy {}
 
 impl<T> std::ops::Deref for Immutable<T> {
@@ -55,7 +65,8 @@ fn deactivating_mutability() {
 
     println!("immutable_config.base_url: {}", immutable_config.base_url);
 
-    let mut mutable_config = immutable_config;
-    // Cannot assign
-    mutable_config.base_url = "https://example.com".to_string();
+    let mutable_config = immutable_config;
+    // Cannot assign, `Immutable` only implements `Deref`
+    // mutable_config.base_url = "https://example.com".to_string();
+    assert_eq!(mutable_config.base_url, "https://example.com");
 }
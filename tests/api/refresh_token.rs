@@ -0,0 +1,108 @@
+use rush_booking::authentication::{decode, verify, JwtResponse};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn login_issues_a_refresh_token() {
+    let app = spawn_app().await;
+
+    let tokens = app.login().await;
+
+    assert!(!tokens.refresh_token.is_empty());
+    assert_ne!(tokens.refresh_token, "refresh_token");
+}
+
+#[tokio::test]
+async fn refresh_token_is_exchanged_for_new_tokens() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let refreshed: JwtResponse = response.json().await.unwrap();
+    assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+    let token = decode(&refreshed.access_token).expect("Failed to decode the access token");
+    assert!(verify(&token, &app.jwt_keys).is_ok());
+    assert_eq!(token.payload.sub, app.test_user.user_id.to_string());
+}
+
+#[tokio::test]
+async fn refresh_returns_401_for_unknown_token() {
+    let app = spawn_app().await;
+
+    let response = app.post_refresh_token("not-a-refresh-token").await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn reusing_a_rotated_token_revokes_the_token_family() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+    let rotated: JwtResponse = app
+        .post_refresh_token(&tokens.refresh_token)
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // The old token leaked and is replayed
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The legitimate holder of the rotated token is logged out as well
+    let response = app.post_refresh_token(&rotated.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn reuse_does_not_revoke_other_sessions() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+    let other_session = app.login().await;
+    app.post_refresh_token(&tokens.refresh_token).await;
+
+    app.post_refresh_token(&tokens.refresh_token).await;
+
+    let response = app.post_refresh_token(&other_session.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn logout_revokes_the_refresh_token() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+
+    let response = app.post_logout(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn logout_returns_401_for_unknown_token() {
+    let app = spawn_app().await;
+
+    let response = app.post_logout("not-a-refresh-token").await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn expired_refresh_token_is_rejected() {
+    let app = spawn_app().await;
+    let tokens = app.login().await;
+    sqlx::query!(
+        "UPDATE refresh_tokens SET expires_at = now() - interval '1 second' WHERE user_id = $1",
+        app.test_user.user_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to expire the refresh token");
+
+    let response = app.post_refresh_token(&tokens.refresh_token).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
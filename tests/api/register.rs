@@ -0,0 +1,202 @@
+use fake::{faker::internet::en::SafeEmail, Fake};
+use uuid::Uuid;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+fn new_user() -> serde_json::Value {
+    serde_json::json!({
+        // Tests share one database, so prefix the fake email to keep it unique
+        "username": format!("{}.{}", Uuid::new_v4(), SafeEmail().fake::<String>()),
+        "password": Uuid::new_v4().to_string(),
+    })
+}
+
+async fn mount_email_server(app: &TestApp) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+}
+
+#[tokio::test]
+async fn register_returns_200_and_sends_a_confirmation_email() {
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.post_register(&new_user()).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    assert_eq!(confirmation_links.html, confirmation_links.plain_text);
+}
+
+#[tokio::test]
+async fn register_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"username": "not-an-email", "password": "correct-horse-battery"}),
+            "invalid email",
+        ),
+        (
+            serde_json::json!({"username": "tom@example.com", "password": "password"}),
+            "too short password",
+        ),
+        (serde_json::json!({"password": "password"}), "missing email"),
+        (
+            serde_json::json!({"username": "tom@example.com"}),
+            "missing password",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.post_register(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not return 400 when the payload was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn register_answers_the_same_for_a_registered_username() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": Uuid::new_v4().to_string(),
+    });
+
+    let response = app.post_register(&body).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let data = get_response_data_from_json::<String>(response).await;
+    assert_eq!(
+        data.message,
+        format!(
+            "A confirmation email has been sent to {}",
+            app.test_user.username
+        )
+    );
+    // The owner is told instead, without a link to confirm anything
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let email_body = String::from_utf8_lossy(&email_request.body);
+    assert!(!email_body.contains("/register/confirm"));
+    // The password of the existing account is unchanged
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn register_again_replaces_the_link_of_a_pending_user() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let body = new_user();
+    app.post_register(&body).await;
+
+    let response = app.post_register(&body).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let email_requests = app.email_server.received_requests().await.unwrap();
+    let first_links = app.get_confirmation_links(&email_requests[0]);
+    let second_links = app.get_confirmation_links(&email_requests[1]);
+    let response = reqwest::get(first_links.html).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let response = reqwest::get(second_links.html).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn register_returns_500_when_the_email_cannot_be_sent() {
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    let body = new_user();
+
+    let response = app.post_register(&body).await;
+
+    assert_eq!(response.status().as_u16(), 500);
+    let saved = sqlx::query!(
+        "SELECT user_id FROM users WHERE username = $1",
+        body["username"].as_str().unwrap()
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .expect("Failed to fetch the user.");
+    assert!(saved.is_none());
+    // Nothing was stored, so a retry registers the user
+    mount_email_server(&app).await;
+    let response = app.post_register(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn registered_users_can_log_in_only_once_confirmed() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    let body = new_user();
+    app.post_register(&body).await;
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    let response = reqwest::get(confirmation_links.html).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn confirmation_links_work_only_once() {
+    let app = spawn_app().await;
+    mount_email_server(&app).await;
+    app.post_register(&new_user()).await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    reqwest::get(confirmation_links.html.clone()).await.unwrap();
+
+    let response = reqwest::get(confirmation_links.html).await.unwrap();
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn confirm_returns_400_without_a_token_and_401_for_an_unknown_token() {
+    let app = spawn_app().await;
+
+    let response = reqwest::get(format!("{}/register/confirm", app.address))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = reqwest::get(format!("{}/register/confirm?token=unknown", app.address))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,205 @@
+use rush_booking::authentication::{decode, JwtResponse, Role};
+use uuid::Uuid;
+
+use crate::helpers::spawn_app;
+
+fn room_body(host_id: Uuid) -> serde_json::Value {
+    serde_json::json!({
+        "name": "Standard room",
+        "description": "Room with city view",
+        "number_of_beds": 2,
+        "host_id": host_id,
+    })
+}
+
+#[tokio::test]
+async fn login_carries_the_role_and_hosts_of_the_user() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![host_id]).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &manager.username,
+            "password": &manager.password,
+        }))
+        .await;
+
+    let tokens: JwtResponse = response.json().await.unwrap();
+    let token = decode(&tokens.access_token).expect("Failed to decode the access token");
+    assert_eq!(token.payload.role, Role::HostManager);
+    assert_eq!(token.payload.host_ids, vec![host_id]);
+}
+
+#[tokio::test]
+async fn guests_cannot_access_the_admin_area() {
+    let app = spawn_app().await;
+    let guest = app.add_user(Role::Guest, vec![]).await;
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(guest.access_token(&app.jwt_keys))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn host_manager_adds_rooms_only_to_their_own_host() {
+    let app = spawn_app().await;
+    let own_host_id = app.create_host().await;
+    let other_host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![own_host_id]).await;
+    let token = manager.access_token(&app.jwt_keys);
+    let test_cases = vec![
+        (own_host_id, 200, "their own host"),
+        (other_host_id, 403, "another host"),
+    ];
+
+    for (host_id, status, error_message) in test_cases {
+        let response = app
+            .api_client
+            .post(format!("{}/admin/rooms", &app.address))
+            .bearer_auth(&token)
+            .json(&room_body(host_id))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Unexpected status when a host manager adds a room to {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn host_manager_cannot_change_rooms_of_another_host() {
+    let app = spawn_app().await;
+    let own_host_id = app.create_host().await;
+    let other_room_id = app.create_room(2).await;
+    let manager = app.add_user(Role::HostManager, vec![own_host_id]).await;
+    let token = manager.access_token(&app.jwt_keys);
+
+    let response = app
+        .api_client
+        .patch(format!("{}/admin/rooms/{}", &app.address, other_room_id))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "number_of_beds": 3 }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = app
+        .api_client
+        .delete(format!("{}/admin/rooms/{}", &app.address, other_room_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn only_admins_add_and_delete_hosts() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![host_id]).await;
+    let token = manager.access_token(&app.jwt_keys);
+
+    let response = app
+        .api_client
+        .post(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "name": "Sheraton", "category": "hotel" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = app
+        .api_client
+        .delete(format!("{}/admin/hosts/{}", &app.address, host_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = app
+        .api_client
+        .patch(format!("{}/admin/hosts/{}", &app.address, host_id))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "name": "Sheraton" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn host_manager_reads_only_their_own_hosts() {
+    let app = spawn_app().await;
+    let own_host_id = app.create_host().await;
+    let other_host_id = app.create_host().await;
+    let manager = app.add_user(Role::HostManager, vec![own_host_id]).await;
+    let token = manager.access_token(&app.jwt_keys);
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let host_ids: Vec<&str> = body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|host| host["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(host_ids, vec![own_host_id.to_string()]);
+
+    let test_cases = vec![
+        (own_host_id, 200, "their own host"),
+        (other_host_id, 403, "another host"),
+    ];
+    for (host_id, status, error_message) in test_cases {
+        let response = app
+            .api_client
+            .get(format!("{}/admin/hosts/{}", &app.address, host_id))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Unexpected status when a host manager gets {}",
+            error_message
+        );
+
+        let response = app
+            .api_client
+            .get(format!("{}/admin/rooms", &app.address))
+            .bearer_auth(&token)
+            .query(&[("host_id", host_id)])
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Unexpected status when a host manager lists the rooms of {}",
+            error_message
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::{Days, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app};
+
+fn days_from_today(days: u64) -> NaiveDate {
+    Utc::now().date_naive() + Days::new(days)
+}
+
+#[tokio::test]
+async fn room_availability_returns_400_for_invalid_query() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+                "guests": 1,
+            }),
+            "missing host id",
+        ),
+        (
+            serde_json::json!({
+                "host_id": host_id,
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+            }),
+            "missing guests",
+        ),
+        (
+            serde_json::json!({
+                "host_id": host_id,
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+                "guests": 0,
+            }),
+            "no guests",
+        ),
+        (
+            serde_json::json!({
+                "host_id": host_id,
+                "check_in": days_from_today(1),
+                "check_out": days_from_today(2),
+                "guests": 40000,
+            }),
+            "more guests than can be compared",
+        ),
+        (
+            serde_json::json!({
+                "host_id": host_id,
+                "check_in": days_from_today(2),
+                "check_out": days_from_today(1),
+                "guests": 1,
+            }),
+            "check-out before check-in",
+        ),
+    ];
+
+    for (invalid_query, error_message) in test_cases {
+        let response = app.get_room_availability(&invalid_query).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when the query was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn room_availability_excludes_booked_and_too_small_rooms() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let booked_room_id = app.create_room_for_host(host_id, 2).await;
+    let free_room_id = app.create_room_for_host(host_id, 3).await;
+    let _small_room_id = app.create_room_for_host(host_id, 1).await;
+    let body = serde_json::json!({
+        "room_id": booked_room_id,
+        "guest_name": "Tom",
+        "guest_email": "tom@example.com",
+        "check_in": days_from_today(1),
+        "check_out": days_from_today(3),
+    });
+    let response = app.post_bookings(&body).await;
+    assert!(response.status().is_success());
+
+    let query = serde_json::json!({
+        "host_id": host_id,
+        "check_in": days_from_today(2),
+        "check_out": days_from_today(4),
+        "guests": 2,
+    });
+    let response = app.get_room_availability(&query).await;
+
+    assert!(response.status().is_success());
+    let rooms = get_response_data_from_json::<Vec<serde_json::Value>>(response)
+        .await
+        .data;
+    let room_ids = rooms
+        .iter()
+        .map(|room| room["id"].as_str().unwrap().parse::<Uuid>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(room_ids, vec![free_room_id]);
+}
+
+#[tokio::test]
+async fn room_availability_includes_room_checked_out_on_the_check_in_day() {
+    let app = spawn_app().await;
+    let host_id = app.create_host().await;
+    let room_id = app.create_room_for_host(host_id, 2).await;
+    let body = serde_json::json!({
+        "room_id": room_id,
+        "guest_name": "Tom",
+        "guest_email": "tom@example.com",
+        "check_in": days_from_today(1),
+        "check_out": days_from_today(3),
+    });
+    let response = app.post_bookings(&body).await;
+    assert!(response.status().is_success());
+
+    let query = serde_json::json!({
+        "host_id": host_id,
+        "check_in": days_from_today(3),
+        "check_out": days_from_today(5),
+        "guests": 2,
+    });
+    let response = app.get_room_availability(&query).await;
+
+    assert!(response.status().is_success());
+    let rooms = get_response_data_from_json::<Vec<serde_json::Value>>(response)
+        .await
+        .data;
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0]["id"].as_str(), Some(room_id.to_string().as_str()));
+}
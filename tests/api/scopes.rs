@@ -0,0 +1,149 @@
+use rush_booking::authentication::{decode, sign, JwtResponse, Role};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn login_grants_every_scope_of_the_role_by_default() {
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            Role::Admin,
+            "rooms:read rooms:write bookings:write users:admin",
+        ),
+        (Role::HostManager, "rooms:read rooms:write bookings:write"),
+        (Role::Guest, ""),
+    ];
+
+    for (role, expected_scope) in test_cases {
+        let user = app.add_user(role, vec![]).await;
+
+        let tokens: JwtResponse = app
+            .post_login(&serde_json::json!({
+                "username": &user.username,
+                "password": &user.password,
+            }))
+            .await
+            .json()
+            .await
+            .unwrap();
+
+        let token = decode(&tokens.access_token).expect("Failed to decode the access token");
+        assert_eq!(tokens.scope, expected_scope, "{:?}", role);
+        assert_eq!(token.payload.scope, expected_scope, "{:?}", role);
+    }
+}
+
+#[tokio::test]
+async fn login_returns_400_for_unknown_scope() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "scope": "rooms:read rooms:delete",
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn read_only_token_cannot_change_inventory() {
+    let app = spawn_app().await;
+    let tokens: JwtResponse = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "scope": "rooms:read",
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tokens.scope, "rooms:read");
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(&tokens.access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .api_client
+        .post(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(&tokens.access_token)
+        .json(&serde_json::json!({ "name": "Sheraton", "category": "hotel" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn refreshed_token_keeps_the_requested_scopes() {
+    let app = spawn_app().await;
+    let tokens: JwtResponse = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "scope": "rooms:read",
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    let refreshed: JwtResponse = app
+        .post_refresh_token(&tokens.refresh_token)
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(refreshed.scope, "rooms:read");
+}
+
+#[tokio::test]
+async fn requested_scopes_are_limited_to_the_role() {
+    let app = spawn_app().await;
+    let manager = app.add_user(Role::HostManager, vec![]).await;
+
+    let tokens: JwtResponse = app
+        .post_login(&serde_json::json!({
+            "username": &manager.username,
+            "password": &manager.password,
+            "scope": "rooms:write users:admin",
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(tokens.scope, "rooms:write");
+}
+
+#[tokio::test]
+async fn token_without_scopes_is_rejected_with_403() {
+    let app = spawn_app().await;
+    let token = sign(
+        &app.jwt_keys
+            .new_payload(app.test_user.user_id.to_string())
+            .set_role(Role::Admin, vec![]),
+        app.jwt_keys.private_key(),
+    )
+    .expect("Failed to sign the token");
+
+    let response = app
+        .api_client
+        .get(format!("{}/admin/hosts", &app.address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 403);
+}
@@ -0,0 +1,277 @@
+use rush_booking::authentication::{totp_code, JwtResponse, MfaChallenge, TotpEnrollment};
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Enable TOTP for the test user, return the secret and the recovery codes
+async fn enable_totp(app: &TestApp) -> (String, Vec<String>) {
+    let response = app.post_enroll_totp(&app.test_user.password).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let enrollment: TotpEnrollment = get_response_data_from_json(response).await.data;
+    let code = totp_code(&enrollment.secret, unix_now()).unwrap();
+
+    let response = app.post_confirm_totp(&code).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let recovery_codes = get_response_data_from_json(response).await.data;
+
+    (enrollment.secret, recovery_codes)
+}
+
+async fn login_challenge(app: &TestApp) -> MfaChallenge {
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response.json().await.expect("Expected an MFA challenge")
+}
+
+#[tokio::test]
+async fn enrollment_returns_a_key_uri_and_recovery_codes_once_confirmed() {
+    let app = spawn_app().await;
+
+    let response = app.post_enroll_totp(&app.test_user.password).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let enrollment: TotpEnrollment = get_response_data_from_json(response).await.data;
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(enrollment
+        .otpauth_uri
+        .contains(&format!("secret={}", enrollment.secret)));
+    let code = totp_code(&enrollment.secret, unix_now()).unwrap();
+    let response = app.post_confirm_totp(&code).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let recovery_codes: Vec<String> = get_response_data_from_json(response).await.data;
+    assert_eq!(recovery_codes.len(), 10);
+}
+
+#[tokio::test]
+async fn confirming_with_a_wrong_code_does_not_enable_totp() {
+    let app = spawn_app().await;
+    let response = app.post_enroll_totp(&app.test_user.password).await;
+    let enrollment: TotpEnrollment = get_response_data_from_json(response).await.data;
+    let stale_code = totp_code(&enrollment.secret, unix_now() - 300).unwrap();
+
+    let response = app.post_confirm_totp(&stale_code).await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    let tokens: Result<JwtResponse, _> = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+        }))
+        .await
+        .json()
+        .await;
+    assert!(
+        tokens.is_ok(),
+        "The login should not require a second factor"
+    );
+}
+
+#[tokio::test]
+async fn enrolling_again_once_enabled_returns_409() {
+    let app = spawn_app().await;
+    enable_totp(&app).await;
+
+    let response = app.post_enroll_totp(&app.test_user.password).await;
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn anonymous_users_cannot_enroll() {
+    let app = spawn_app().await;
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/totp", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_with_totp_enabled_requires_a_code() {
+    let app = spawn_app().await;
+    let (secret, _) = enable_totp(&app).await;
+
+    let challenge = login_challenge(&app).await;
+    // The enrollment used up the code of the current time step
+    let code = totp_code(&secret, unix_now() + 30).unwrap();
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": &challenge.challenge_token,
+            "code": &code,
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let tokens: JwtResponse = response.json().await.unwrap();
+    assert_eq!(tokens.token_type, "Bearer");
+}
+
+#[tokio::test]
+async fn totp_code_cannot_be_replayed() {
+    let app = spawn_app().await;
+    let (secret, _) = enable_totp(&app).await;
+    let code = totp_code(&secret, unix_now() + 30).unwrap();
+    let challenge = login_challenge(&app).await;
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": &challenge.challenge_token,
+            "code": &code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let challenge = login_challenge(&app).await;
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": &challenge.challenge_token,
+            "code": &code,
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn recovery_code_can_only_be_used_once() {
+    let app = spawn_app().await;
+    let (_, recovery_codes) = enable_totp(&app).await;
+    let test_cases = vec![(200, "the first time"), (401, "the second time")];
+
+    for (status, error_message) in test_cases {
+        let challenge = login_challenge(&app).await;
+        let response = app
+            .post_login_totp(&serde_json::json!({
+                "challenge_token": &challenge.challenge_token,
+                "code": recovery_codes[0].to_uppercase(),
+            }))
+            .await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            status,
+            "Unexpected status when a recovery code is used {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn challenge_is_dropped_after_too_many_wrong_codes() {
+    let app = spawn_app().await;
+    let (secret, _) = enable_totp(&app).await;
+    let challenge = login_challenge(&app).await;
+    let wrong_code = totp_code(&secret, unix_now() - 300).unwrap();
+    for _ in 0..5 {
+        let response = app
+            .post_login_totp(&serde_json::json!({
+                "challenge_token": &challenge.challenge_token,
+                "code": &wrong_code,
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": &challenge.challenge_token,
+            "code": totp_code(&secret, unix_now() + 30).unwrap(),
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_totp_returns_429_while_the_account_is_locked_out() {
+    let app = spawn_app().await;
+    let (secret, _) = enable_totp(&app).await;
+    let challenge = login_challenge(&app).await;
+    for _ in 0..5 {
+        let response = app
+            .post_login(&serde_json::json!({
+                "username": &app.test_user.username,
+                "password": uuid::Uuid::new_v4().to_string(),
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": &challenge.challenge_token,
+            "code": totp_code(&secret, unix_now() + 30).unwrap(),
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().get("Retry-After").is_some());
+}
+
+#[tokio::test]
+async fn unknown_challenge_is_rejected() {
+    let app = spawn_app().await;
+    let (secret, _) = enable_totp(&app).await;
+
+    let response = app
+        .post_login_totp(&serde_json::json!({
+            "challenge_token": "unknown",
+            "code": totp_code(&secret, unix_now() + 30).unwrap(),
+        }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn enrolling_returns_400_for_a_wrong_password() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_enroll_totp(&uuid::Uuid::new_v4().to_string())
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn api_keys_and_restricted_sessions_cannot_enroll() {
+    let app = spawn_app().await;
+    let api_key = app
+        .create_api_key("rooms:read rooms:write bookings:write users:admin")
+        .await;
+    let restricted_token = app.restricted_access_token("rooms:read").await;
+    let body = serde_json::json!({ "password": &app.test_user.password });
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/totp", &app.address))
+        .header("X-Api-Key", &api_key)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .api_client
+        .post(format!("{}/account/totp", &app.address))
+        .bearer_auth(&restricted_token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
@@ -0,0 +1,364 @@
+use chrono::{Days, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::helpers::{get_response_data_from_json, spawn_app, TestApp};
+
+fn days_from_today(days: u64) -> NaiveDate {
+    Utc::now().date_naive() + Days::new(days)
+}
+
+#[derive(serde::Deserialize)]
+struct ModifiedBooking {
+    room_id: Uuid,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    total_price: i64,
+}
+
+async fn create_priced_room(app: &TestApp, price_per_night: i64) -> Uuid {
+    let free_cancellation = serde_json::json!({
+        "kind": "refundable",
+        "free_until_days": 0,
+        "penalty_percent": 0,
+    });
+    create_room_with_policy(app, price_per_night, free_cancellation).await
+}
+
+async fn create_room_with_policy(
+    app: &TestApp,
+    price_per_night: i64,
+    cancellation_policy: serde_json::Value,
+) -> Uuid {
+    let host_id = app.create_host().await;
+    let response = app
+        .post_rooms(&serde_json::json!({
+            "name": "Standard room",
+            "description": "Room with city view",
+            "number_of_beds": 2,
+            "host_id": host_id,
+            "price_per_night": price_per_night,
+            "cancellation_policy": cancellation_policy,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    get_response_data_from_json(response).await.data
+}
+
+#[tokio::test]
+async fn update_booking_moves_the_stay_and_reprices_it() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let suite_id = create_priced_room(&app, 25_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "room_id": suite_id,
+                "check_in": days_from_today(20),
+                "check_out": days_from_today(23),
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let modified: ModifiedBooking = get_response_data_from_json(response).await.data;
+    assert_eq!(modified.room_id, suite_id);
+    assert_eq!(modified.check_in, days_from_today(20));
+    assert_eq!(modified.check_out, days_from_today(23));
+    assert_eq!(modified.total_price, 75_000);
+    let saved = sqlx::query!(
+        "SELECT room_id, check_in, total_price FROM bookings WHERE id = $1",
+        booking_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the booking.");
+    assert_eq!(saved.room_id, suite_id);
+    assert_eq!(saved.check_in, days_from_today(20));
+    assert_eq!(saved.total_price, 75_000);
+    // The nights of the previous stay are free again
+    app.book_room(room_id, 10, 12).await;
+}
+
+#[tokio::test]
+async fn update_booking_can_overlap_its_own_nights() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_out": days_from_today(14),
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let modified: ModifiedBooking = get_response_data_from_json(response).await.data;
+    assert_eq!(modified.room_id, room_id);
+    assert_eq!(modified.check_in, days_from_today(10));
+    assert_eq!(modified.total_price, 40_000);
+}
+
+#[tokio::test]
+async fn update_booking_keeps_a_history_of_modifications() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+    for (check_in, check_out) in [(20, 21), (30, 33)] {
+        let response = app
+            .patch_booking(
+                booking_id,
+                &serde_json::json!({
+                    "guest_email": "tom@example.com",
+                    "check_in": days_from_today(check_in),
+                    "check_out": days_from_today(check_out),
+                }),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let modifications = sqlx::query!(
+        r#"
+        SELECT previous_check_in, previous_total_price, check_in, total_price
+        FROM booking_modifications
+        WHERE booking_id = $1
+        ORDER BY modified_at
+        "#,
+        booking_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch the modifications.");
+
+    let modifications: Vec<_> = modifications
+        .into_iter()
+        .map(|m| {
+            (
+                m.previous_check_in,
+                m.previous_total_price,
+                m.check_in,
+                m.total_price,
+            )
+        })
+        .collect();
+    assert_eq!(
+        modifications,
+        vec![
+            (days_from_today(10), 20_000, days_from_today(20), 10_000),
+            (days_from_today(20), 10_000, days_from_today(30), 30_000),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn update_booking_takes_the_policy_of_the_new_room() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let non_refundable_id =
+        create_room_with_policy(&app, 8_000, serde_json::json!({ "kind": "non_refundable" })).await;
+    let booking_id = app.book_room(room_id, 30, 32).await;
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "room_id": non_refundable_id,
+            }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_cancel_booking(booking_id, "tom@example.com").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let cancelled: serde_json::Value = get_response_data_from_json(response).await.data;
+    assert_eq!(cancelled["penalty"], 16_000);
+    assert_eq!(cancelled["refund"], 0);
+}
+
+#[tokio::test]
+async fn update_booking_to_the_same_stay_changes_nothing() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+    app.patch_room(room_id, &serde_json::json!({ "price_per_night": 50_000 }))
+        .await;
+
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "room_id": room_id,
+                "check_in": days_from_today(10),
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let modified: ModifiedBooking = get_response_data_from_json(response).await.data;
+    assert_eq!(modified.total_price, 20_000);
+    let modifications = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM booking_modifications WHERE booking_id = $1",
+        booking_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the modifications.");
+    assert_eq!(modifications, Some(0));
+}
+
+#[tokio::test]
+async fn update_booking_returns_409_and_keeps_the_booking_when_unavailable() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    app.book_room(room_id, 10, 12).await;
+    let booking_id = app.book_room(room_id, 20, 22).await;
+
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_in": days_from_today(11),
+                "check_out": days_from_today(13),
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 409);
+    let saved = sqlx::query!(
+        "SELECT check_in, check_out, total_price FROM bookings WHERE id = $1",
+        booking_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch the booking.");
+    assert_eq!(
+        (saved.check_in, saved.check_out, saved.total_price),
+        (days_from_today(20), days_from_today(22), 20_000)
+    );
+    let modifications = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM booking_modifications WHERE booking_id = $1",
+        booking_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to count the modifications.");
+    assert_eq!(modifications, Some(0));
+}
+
+#[tokio::test]
+async fn update_booking_returns_409_once_cancelled() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+    app.post_cancel_booking(booking_id, "tom@example.com").await;
+
+    let response = app
+        .patch_booking(
+            booking_id,
+            &serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_out": days_from_today(14),
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[tokio::test]
+async fn update_booking_returns_404_for_another_guest_or_unknown_booking_or_room() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+    let test_cases = vec![
+        (
+            booking_id,
+            serde_json::json!({
+                "guest_email": "jerry@example.com",
+                "check_out": days_from_today(14),
+            }),
+            "the email of another guest",
+        ),
+        (
+            Uuid::new_v4(),
+            serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_out": days_from_today(14),
+            }),
+            "an unknown booking",
+        ),
+        (
+            booking_id,
+            serde_json::json!({
+                "guest_email": "tom@example.com",
+                "room_id": Uuid::new_v4(),
+            }),
+            "an unknown room",
+        ),
+    ];
+
+    for (booking_id, body, error_message) in test_cases {
+        let response = app.patch_booking(booking_id, &body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            404,
+            "The API did not fail with 404 Not Found with {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn update_booking_returns_400_for_invalid_data() {
+    let app = spawn_app().await;
+    let room_id = create_priced_room(&app, 10_000).await;
+    let booking_id = app.book_room(room_id, 10, 12).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({ "guest_email": "tom@example.com" }),
+            "nothing to update",
+        ),
+        (
+            serde_json::json!({ "check_out": days_from_today(14) }),
+            "missing guest email",
+        ),
+        (
+            serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_out": days_from_today(9),
+            }),
+            "check-out before the current check-in",
+        ),
+        (
+            serde_json::json!({
+                "guest_email": "tom@example.com",
+                "check_in": Utc::now().date_naive() - Days::new(1),
+            }),
+            "check-in in the past",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        let response = app.patch_booking(booking_id, &body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "The API did not fail with 400 Bad Request when {}",
+            error_message
+        );
+    }
+}